@@ -1,6 +1,7 @@
 extern crate seer;
 extern crate env_logger;
 extern crate log_settings;
+#[macro_use]
 extern crate log;
 
 fn init_logger() {
@@ -36,20 +37,75 @@ fn init_logger() {
     builder.init().unwrap();
 }
 
+/// Reads the search strategy from the `SEER_SEARCH` environment variable.
+fn search_strategy_from_env() -> ::seer::SearchStrategyKind {
+    use seer::SearchStrategyKind::*;
+    match std::env::var("SEER_SEARCH") {
+        Err(_) => DepthFirst,
+        Ok(s) => match &s[..] {
+            "dfs" => DepthFirst,
+            "bfs" => BreadthFirst,
+            "random" => RandomPath(seed_from_env()),
+            "fewest-constraints" => FewestConstraints,
+            "covering-new" => CoveringNew,
+            _ => panic!("unknown SEER_SEARCH value {:?}. expected one of \
+                         dfs, bfs, random, fewest-constraints, covering-new", s),
+        }
+    }
+}
+
+/// Reads the seed of random-path search from the `SEER_SEED` environment variable,
+/// or derives one from the current time. Logs the seed so that a run can be repeated.
+fn seed_from_env() -> u64 {
+    let seed = match std::env::var("SEER_SEED") {
+        Ok(s) => match s.parse() {
+            Ok(seed) => seed,
+            _ => panic!("SEER_SEED must be an unsigned integer, got {:?}", s),
+        },
+        Err(_) => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("system time is before the unix epoch");
+            now.as_secs() ^ ((now.subsec_nanos() as u64) << 32)
+        }
+    };
+    info!("random-path search with SEER_SEED={}", seed);
+    seed
+}
+
+/// Reads the number of worker processes from the `SEER_WORKERS` environment variable.
+fn workers_from_env() -> usize {
+    match std::env::var("SEER_WORKERS") {
+        Err(_) => 1,
+        Ok(s) => match s.parse() {
+            Ok(n) if n > 0 => n,
+            _ => panic!("SEER_WORKERS must be a positive integer, got {:?}", s),
+        }
+    }
+}
+
 fn main() {
     init_logger();
     let consumer = |complete: ::seer::ExecutionComplete | {
         println!("{:?}", complete);
         println!("as string: {:?}", ::std::str::from_utf8(&complete.input));
-        if let Err(_) = complete.result {
-            println!("hit an error. halting");
-            false
-        } else {
-            true
+        match complete.result {
+            Err(::seer::StaticEvalError::Unimplemented(_)) |
+            Err(::seer::StaticEvalError::SolverUnknown) => {
+                println!("path incomplete. continuing");
+                true
+            }
+            Err(_) => {
+                println!("hit an error. halting");
+                false
+            }
+            Ok(()) => true,
         }
     };
 
     ::seer::ExecutionConfig::new()
         .consumer(consumer)
+        .search_strategy(search_strategy_from_env())
+        .workers(workers_from_env())
         .run(::std::env::args().collect());
 }
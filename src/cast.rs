@@ -1,14 +1,15 @@
+use rustc::mir;
 use rustc::ty::{self, Ty};
 use syntax::ast::{FloatTy, IntTy, UintTy};
 
 use error::{EvalResult, EvalError};
 use eval_context::EvalContext;
 use memory::{Pointer, SByte};
-use value::PrimVal;
+use value::{PrimVal, PrimValKind};
 
 impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     pub(super) fn cast_primval(
-        &self,
+        &mut self,
         val: PrimVal,
         src_ty: Ty<'tcx>,
         dest_ty: Ty<'tcx>
@@ -17,27 +18,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
         use value::PrimValKind::*;
         match val {
-            PrimVal::Abstract(mut sbytes) => {
+            PrimVal::Abstract(sbytes) => {
                 let dest_kind = self.ty_to_primval_kind(dest_ty)?;
-                if kind.is_int() && dest_kind.is_int() {
-                    let src_size = kind.num_bytes();
-                    let dest_size = dest_kind.num_bytes();
-                    for idx in dest_size .. src_size {
-                        sbytes[idx] = SByte::Concrete(0);
-                    }
-                    // TODO(optimization): check to see if the cast has made
-                    // the value concrete.
-                    Ok(PrimVal::Abstract(sbytes))
-                } else {
-                    match (kind, dest_kind) {
-                        (U8, Char) => {
-                            Ok(PrimVal::Abstract(sbytes))
-                        }
-                        _ => {
-                            unimplemented!()
-                        }
-                    }
-                }
+                self.cast_abstract(sbytes, kind, dest_kind)
             }
             _ => {
                 match kind {
@@ -54,6 +37,105 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         }
     }
 
+    pub(super) fn cast_abstract(
+        &mut self,
+        mut sbytes: [SByte; 16],
+        kind: PrimValKind,
+        dest_kind: PrimValKind,
+    ) -> EvalResult<'tcx, PrimVal> {
+        use value::PrimValKind::*;
+        let val = PrimVal::Abstract(sbytes);
+
+        if kind.is_int() && dest_kind.is_int() {
+            let src_size = kind.num_bytes();
+            let dest_size = dest_kind.num_bytes();
+            let fill = if src_size < dest_size && kind.is_signed_int() {
+                self.abstract_sign_byte(sbytes[src_size - 1])?
+            } else {
+                SByte::Concrete(0)
+            };
+            for idx in src_size .. dest_size {
+                sbytes[idx] = fill;
+            }
+            for idx in dest_size .. 16 {
+                sbytes[idx] = SByte::Concrete(0);
+            }
+            // TODO(optimization): check to see if the cast has made
+            // the value concrete.
+            return Ok(PrimVal::Abstract(sbytes));
+        }
+
+        match (kind, dest_kind) {
+            (Bool, _) if dest_kind.is_int() => {
+                self.memory.constraints.add_bool_to_int_constraint(val)
+            }
+
+            (_, Bool) if kind.is_int() => {
+                // Only 0 and 1 are valid bools.
+                let unsigned = PrimValKind::from_uint_size(kind.num_bytes() as u64);
+                let valid = self.memory.constraints.add_binop_constraint(
+                    mir::BinOp::Le, val, PrimVal::Bytes(1), unsigned)?;
+                self.require_valid(valid, |_, _| Ok(EvalError::InvalidBool))?;
+                self.memory.constraints.add_binop_constraint(
+                    mir::BinOp::Ne, val, PrimVal::Bytes(0), kind)
+            }
+
+            (Char, _) if dest_kind.is_int() => self.cast_abstract(sbytes, U32, dest_kind),
+
+            (U8, Char) => self.cast_abstract(sbytes, U8, U32),
+
+            (_, Char) if kind.is_int() => {
+                // Compare at a width that can hold every char, then truncate.
+                let (sbytes, wide) = if kind.num_bytes() < 4 {
+                    match self.cast_abstract(sbytes, kind, U32)? {
+                        PrimVal::Abstract(sbytes) => (sbytes, U32),
+                        _ => bug!("abstract cast produced a concrete value"),
+                    }
+                } else {
+                    (sbytes, PrimValKind::from_uint_size(kind.num_bytes() as u64))
+                };
+                self.ensure_valid_abstract_char(PrimVal::Abstract(sbytes), wide)?;
+                self.cast_abstract(sbytes, wide, U32)
+            }
+
+            (F32, F32) | (F64, F64) => Ok(val),
+
+            (F32, F64) | (F64, F32) => {
+                self.memory.constraints.add_float_cast_constraint(val, kind, dest_kind)
+            }
+
+            (F32, _) | (F64, _) if dest_kind.is_int() => {
+                self.memory.constraints.add_float_cast_constraint(val, kind, dest_kind)
+            }
+
+            (_, F32) | (_, F64) if kind.is_int() => {
+                self.memory.constraints.add_float_cast_constraint(val, kind, dest_kind)
+            }
+
+            _ => {
+                let msg = format!("abstract cast from {:?} to {:?}", kind, dest_kind);
+                Err(EvalError::Unimplemented(msg))
+            }
+        }
+    }
+
+    /// The byte that sign-extends a value whose most significant byte is `sbyte`.
+    fn abstract_sign_byte(&mut self, sbyte: SByte) -> EvalResult<'tcx, SByte> {
+        match sbyte {
+            SByte::Concrete(b) => Ok(SByte::Concrete(((b as i8) >> 7) as u8)),
+            SByte::Abstract(_) => {
+                let mut buffer = [SByte::Concrete(0); 16];
+                buffer[0] = sbyte;
+                let fill = self.memory.constraints.add_binop_constraint(
+                    mir::BinOp::Shr, PrimVal::Abstract(buffer), PrimVal::Bytes(7), PrimValKind::I8)?;
+                match fill {
+                    PrimVal::Abstract(fill) => Ok(fill[0]),
+                    _ => bug!("abstract binop produced a concrete value"),
+                }
+            }
+        }
+    }
+
     fn cast_signed_int(&self, val: i128, ty: ty::Ty<'tcx>) -> EvalResult<'tcx, PrimVal> {
         self.cast_int(val as u128, ty, val < 0)
     }
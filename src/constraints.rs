@@ -1,6 +1,15 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use std::time::Duration;
+
 use rustc::mir;
 use z3;
 
+use error::{EvalError, EvalResult};
+use float;
 use memory::{AbstractVariable, SByte};
 use value::{PrimVal, PrimValKind};
 
@@ -24,9 +33,262 @@ pub struct ConstraintContext {
     variables: Vec<(VarType, VarOrigin)>,
 
     constraints: Vec<Constraint>,
+
+    /// For each entry of `constraints`, an id that is unique among all clones
+    /// of this context.
+    ids: Vec<u64>,
+
+    /// For each entry of `constraints`, one of its variables, or `None` if it
+    /// has none. All variables of a constraint are in the same set of
+    /// `var_sets`, so this one decides which set the constraint belongs to.
+    first_vars: Vec<Option<AbstractVariable>>,
+
+    /// Sets of variables that are connected through `constraints`.
+    var_sets: VarSets,
+
+    /// Shared by all clones of this context.
+    solver: Rc<RefCell<IncrementalSolver>>,
+}
+
+/// A union-find structure over variable ids. Two variables are in the same set
+/// if a chain of constraints connects them.
+#[derive(Clone, Debug)]
+struct VarSets {
+    parent: Vec<u32>,
+    rank: Vec<u8>,
+}
+
+impl VarSets {
+    fn new() -> Self {
+        VarSets { parent: Vec::new(), rank: Vec::new() }
+    }
+
+    fn add(&mut self) {
+        let id = self.parent.len() as u32;
+        self.parent.push(id);
+        self.rank.push(0);
+    }
+
+    fn find(&self, var: AbstractVariable) -> u32 {
+        let mut v = var.0;
+        while self.parent[v as usize] != v {
+            v = self.parent[v as usize];
+        }
+        v
+    }
+
+    fn union(&mut self, a: AbstractVariable, b: AbstractVariable) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (rank_a, rank_b) = (self.rank[a as usize], self.rank[b as usize]);
+        if rank_a < rank_b {
+            self.parent[a as usize] = b;
+        } else {
+            self.parent[b as usize] = a;
+            if rank_a == rank_b {
+                self.rank[a as usize] += 1;
+            }
+        }
+    }
+}
+
+/// A z3 solver that is shared by all clones of a `ConstraintContext`.
+///
+/// It keeps the constraints of the path that it last answered a query for
+/// asserted, each in its own scope. A query for a path only needs to pop the
+/// scopes that the path does not share and assert the constraints that are new,
+/// which is cheap for a path that forked off recently or has grown by a few
+/// constraints since its last query.
+struct IncrementalSolver {
+    z3: Z3,
+
+    /// The ids of the asserted constraints, in the order of their scopes.
+    asserted: Vec<u64>,
+
+    next_id: u64,
+
+    cache: QueryCache,
+}
+
+/// Answers to earlier feasibility queries, shared by all paths.
+struct QueryCache {
+    /// Keyed by the ids of the path constraints in the query and the
+    /// constraints that were checked together with them.
+    results: HashMap<(Vec<u64>, Vec<Constraint>), bool>,
+
+    /// Recent models, most recent first. A query that one of them satisfies is
+    /// feasible without asking z3.
+    models: VecDeque<Assignment>,
+}
+
+/// Values of bitvector and boolean variables, by variable id.
+type Assignment = HashMap<u32, u64>;
+
+const MAX_CACHED_RESULTS: usize = 100_000;
+const MAX_CACHED_MODELS: usize = 16;
+
+impl QueryCache {
+    fn new() -> Self {
+        QueryCache { results: HashMap::new(), models: VecDeque::new() }
+    }
+
+    fn insert_result(&mut self, key: (Vec<u64>, Vec<Constraint>), feasible: bool) {
+        if self.results.len() >= MAX_CACHED_RESULTS {
+            self.results.clear();
+        }
+        self.results.insert(key, feasible);
+    }
+
+    fn insert_model(&mut self, model: Assignment) {
+        if self.models.len() >= MAX_CACHED_MODELS {
+            self.models.pop_back();
+        }
+        self.models.push_front(model);
+    }
+
+    /// Whether a cached model satisfies all of `constraints`.
+    fn has_model_for<'c, I>(&self, constraints: I) -> bool
+        where I: Iterator<Item = &'c Constraint> + Clone
+    {
+        self.models.iter().any(|model| {
+            constraints.clone().all(|c| eval_constraint(model, c) == Some(true))
+        })
+    }
+}
+
+/// A z3 context together with the solver that uses it.
+struct Z3 {
+    // Borrows `*ctx`, so it has to be dropped first.
+    solver: Option<z3::Solver<'static>>,
+    ctx: *mut z3::Context,
+}
+
+impl Z3 {
+    fn new(cfg: &z3::Config) -> Self {
+        let ctx = Box::into_raw(Box::new(z3::Context::new(cfg)));
+        let solver = z3::Solver::new(unsafe { &*ctx });
+        Z3 { solver: Some(solver), ctx: ctx }
+    }
+
+    /// The context lives as long as `self`. Tying its lifetime to the borrow of
+    /// `self` keeps asts and models built from it from outliving it.
+    fn ctx(&self) -> &z3::Context {
+        unsafe { &*self.ctx }
+    }
+
+    fn solver<'a>(&'a self) -> &'a z3::Solver<'a> {
+        self.solver.as_ref().expect("solver already dropped")
+    }
+}
+
+impl Drop for Z3 {
+    fn drop(&mut self) {
+        self.solver.take();
+        unsafe { drop(Box::from_raw(self.ctx)); }
+    }
+}
+
+impl IncrementalSolver {
+    fn new(timeout: Option<Duration>) -> Self {
+        let mut cfg = z3::Config::new();
+        if let Some(timeout) = timeout {
+            let msec = timeout.as_secs() * 1000 + (timeout.subsec_nanos() / 1_000_000) as u64;
+            cfg.set_timeout_msec(msec);
+        }
+        IncrementalSolver {
+            z3: Z3::new(&cfg),
+            asserted: Vec::new(),
+            next_id: 0,
+            cache: QueryCache::new(),
+        }
+    }
+
+    fn fresh_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Asserts the constraints of `cx` for which `needed` is true.
+    ///
+    /// Constraints of `cx` that are already asserted stay asserted even if they
+    /// are not needed; they hold on the path anyway. Only constraints that are
+    /// needed and not yet asserted get pushed, so the scopes stay in path order
+    /// and the next query on the same path can reuse them.
+    fn sync<'tcx>(&mut self, cx: &ConstraintContext, needed: &[bool]) -> EvalResult<'tcx> {
+        // Keep the asserted constraints up to the first one that is not on the
+        // path, or that would have to come after a needed one that is missing.
+        let mut kept = 0;
+        let mut next = 0;
+        while next < cx.ids.len() {
+            if kept < self.asserted.len() && self.asserted[kept] == cx.ids[next] {
+                kept += 1;
+            } else if needed[next] {
+                break;
+            }
+            next += 1;
+        }
+        if self.asserted.len() > kept {
+            self.z3.solver().pop((self.asserted.len() - kept) as u32);
+            self.asserted.truncate(kept);
+        }
+
+        let ctx = self.z3.ctx();
+        for idx in next..cx.ids.len() {
+            if needed[idx] {
+                let ast = cx.constraint_to_ast(ctx, cx.constraints[idx])?;
+                self.z3.solver().push();
+                self.z3.solver().assert(&ast);
+                self.asserted.push(cx.ids[idx]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the constraints of `cx` for which `needed` is true together with
+    /// `extra`. If they are satisfiable, returns what `f` computes from a model
+    /// of them. Returns `EvalError::SolverUnknown` if z3 cannot decide, for
+    /// example because the check runs into the timeout.
+    fn query<'tcx, F, R>(
+        &mut self,
+        cx: &ConstraintContext,
+        needed: &[bool],
+        extra: &[Constraint],
+        f: F)
+        -> EvalResult<'tcx, Option<R>>
+        where F: for<'c> FnOnce(&'c z3::Context, &z3::Model<'c>) -> EvalResult<'tcx, R>
+    {
+        self.sync(cx, needed)?;
+        let ctx = self.z3.ctx();
+        let mut asts = Vec::new();
+        for c in extra {
+            asts.push(cx.constraint_to_ast(ctx, *c)?);
+        }
+
+        let solver = self.z3.solver();
+        solver.push();
+        for ast in &asts {
+            solver.assert(ast);
+        }
+        let result = match solver.check_sat() {
+            z3::SatResult::Sat => f(ctx, &solver.get_model()).map(Some),
+            z3::SatResult::Unsat => Ok(None),
+            z3::SatResult::Unknown => Err(EvalError::SolverUnknown),
+        };
+        solver.pop(1);
+        result
+    }
+}
+
+impl fmt::Debug for IncrementalSolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IncrementalSolver {{ {} constraints asserted }}", self.asserted.len())
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Constraint {
     Binop {
         operator: mir::BinOp,
@@ -47,12 +309,108 @@ pub enum Constraint {
 
     Compare { op: mir::BinOp, kind: PrimValKind, lhs: PrimVal, rhs: PrimVal, },
 
+    /// lhs = whether op(rhs_operand1, rhs_operand2) overflows. lhs is a bool.
+    Overflow {
+        operator: mir::BinOp,
+        kind: PrimValKind,
+        rhs_operand1: PrimVal,
+        rhs_operand2: PrimVal,
+        lhs: PrimVal,
+    },
+
+    /// lhs = op(operand), where op counts bits of operand. lhs has the kind of operand.
+    BitCount {
+        op: BitCount,
+        kind: PrimValKind,
+        operand: PrimVal,
+        lhs: PrimVal,
+    },
+
     /// array[index] = value
     ArrayElement {
         array: AbstractVariable,
         index: PrimVal,
         value: SByte,
     },
+
+    /// lhs = operand as to, where at least one of from and to is a float kind.
+    FloatCast {
+        from: PrimValKind,
+        to: PrimValKind,
+        operand: PrimVal,
+        lhs: PrimVal,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BitCount {
+    /// The number of set bits.
+    Ones,
+
+    /// The number of zero bits above the highest set bit.
+    LeadingZeros,
+
+    /// The number of zero bits below the lowest set bit.
+    TrailingZeros,
+}
+
+// `mir::BinOp` and `mir::UnOp` do not implement `Hash`.
+impl Hash for Constraint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Constraint::Binop { operator, kind, rhs_operand1, rhs_operand2, lhs, lhs_kind } => {
+                0u8.hash(state);
+                (operator as u8).hash(state);
+                kind.hash(state);
+                rhs_operand1.hash(state);
+                rhs_operand2.hash(state);
+                lhs.hash(state);
+                lhs_kind.hash(state);
+            }
+            Constraint::Unop { operator, kind, operand, lhs } => {
+                1u8.hash(state);
+                (operator as u8).hash(state);
+                kind.hash(state);
+                operand.hash(state);
+                lhs.hash(state);
+            }
+            Constraint::Compare { op, kind, lhs, rhs } => {
+                2u8.hash(state);
+                (op as u8).hash(state);
+                kind.hash(state);
+                lhs.hash(state);
+                rhs.hash(state);
+            }
+            Constraint::ArrayElement { array, index, value } => {
+                3u8.hash(state);
+                array.hash(state);
+                index.hash(state);
+                value.hash(state);
+            }
+            Constraint::Overflow { operator, kind, rhs_operand1, rhs_operand2, lhs } => {
+                4u8.hash(state);
+                (operator as u8).hash(state);
+                kind.hash(state);
+                rhs_operand1.hash(state);
+                rhs_operand2.hash(state);
+                lhs.hash(state);
+            }
+            Constraint::BitCount { op, kind, operand, lhs } => {
+                5u8.hash(state);
+                op.hash(state);
+                kind.hash(state);
+                operand.hash(state);
+                lhs.hash(state);
+            }
+            Constraint::FloatCast { from, to, operand, lhs } => {
+                6u8.hash(state);
+                from.hash(state);
+                to.hash(state);
+                operand.hash(state);
+                lhs.hash(state);
+            }
+        }
+    }
 }
 
 impl Constraint {
@@ -83,6 +441,50 @@ impl Constraint {
     pub fn new_compare(op: mir::BinOp, kind: PrimValKind, lhs: PrimVal, rhs: PrimVal) -> Self {
         Constraint::Compare { op, kind, lhs, rhs }
     }
+
+    /// The abstract variables that this constraint mentions.
+    fn variables(&self) -> Vec<AbstractVariable> {
+        let mut vars = Vec::new();
+        {
+            let mut add_primval = |val: PrimVal| {
+                if let PrimVal::Abstract(sbytes) = val {
+                    for sbyte in &sbytes {
+                        if let SByte::Abstract(v) = *sbyte {
+                            vars.push(v);
+                        }
+                    }
+                }
+            };
+            match *self {
+                Constraint::Binop { rhs_operand1, rhs_operand2, lhs, .. } |
+                Constraint::Overflow { rhs_operand1, rhs_operand2, lhs, .. } => {
+                    add_primval(rhs_operand1);
+                    add_primval(rhs_operand2);
+                    add_primval(lhs);
+                }
+                Constraint::Unop { operand, lhs, .. } |
+                Constraint::BitCount { operand, lhs, .. } |
+                Constraint::FloatCast { operand, lhs, .. } => {
+                    add_primval(operand);
+                    add_primval(lhs);
+                }
+                Constraint::Compare { lhs, rhs, .. } => {
+                    add_primval(lhs);
+                    add_primval(rhs);
+                }
+                Constraint::ArrayElement { index, .. } => {
+                    add_primval(index);
+                }
+            }
+        }
+        if let Constraint::ArrayElement { array, value, .. } = *self {
+            vars.push(array);
+            if let SByte::Abstract(v) = value {
+                vars.push(v);
+            }
+        }
+        vars
+    }
 }
 
 impl ConstraintContext {
@@ -90,35 +492,70 @@ impl ConstraintContext {
         ConstraintContext {
             variables: Vec::new(),
             constraints: Vec::new(),
+            ids: Vec::new(),
+            first_vars: Vec::new(),
+            var_sets: VarSets::new(),
+            solver: Rc::new(RefCell::new(IncrementalSolver::new(None))),
         }
     }
 
     fn allocate_abstract_var(&mut self, var_type: VarType, origin: VarOrigin) -> AbstractVariable {
         let id = self.variables.len() as u32;
         self.variables.push((var_type, origin));
+        self.var_sets.add();
         AbstractVariable(id)
     }
 
+    /// Whether `sbyte` is an abstract bool, as opposed to a concrete byte or an
+    /// abstract byte.
+    pub fn is_abstract_bool(&self, sbyte: SByte) -> bool {
+        match sbyte {
+            SByte::Abstract(v) => match self.variables[v.0 as usize].0 {
+                VarType::Bool => true,
+                _ => false,
+            },
+            SByte::Concrete(_) => false,
+        }
+    }
+
     pub fn fresh_stdin_byte(&mut self) -> SByte {
         SByte::Abstract(self.allocate_abstract_var(VarType::BitVec8, VarOrigin::StdIn))
     }
 
+    /// Gives up on solver queries that take longer than `timeout`. Must be
+    /// called before any constraints are added.
+    pub fn set_solver_timeout(&mut self, timeout: Duration) {
+        assert!(self.constraints.is_empty(), "solver timeout set after constraints were added");
+        self.solver = Rc::new(RefCell::new(IncrementalSolver::new(Some(timeout))));
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
     pub fn push_constraint(&mut self, constraint: Constraint) {
+        let vars = constraint.variables();
+        for pair in vars.windows(2) {
+            self.var_sets.union(pair[0], pair[1]);
+        }
+        let id = self.solver.borrow_mut().fresh_id();
+        self.ids.push(id);
+        self.first_vars.push(vars.first().cloned());
         self.constraints.push(constraint);
     }
 
     /// Creates a fresh abstract PrimVal `X` and adds a constraint
     /// `X == rhs_operand1 binop rhs_operand2`. Returns `X`.
-    pub fn add_binop_constraint(
+    pub fn add_binop_constraint<'tcx>(
         &mut self,
         bin_op: mir::BinOp,
         rhs_operand1: PrimVal,
         rhs_operand2: PrimVal,
-        kind: PrimValKind) -> PrimVal {
+        kind: PrimValKind) -> EvalResult<'tcx, PrimVal> {
 
         use value::PrimValKind::*;
 
-        let mut buffer = [SByte::Concrete(0); 8];
+        let mut buffer = [SByte::Concrete(0); 16];
 
         let (num_bytes, var_type, lhs_kind) = match (bin_op, kind) {
             (mir::BinOp::Eq, _) |
@@ -132,7 +569,13 @@ impl ConstraintContext {
             (_, U16) | (_, I16) => (2, VarType::BitVec8, kind),
             (_, U32) | (_, I32) => (4, VarType::BitVec8, kind),
             (_, U64) | (_, I64) => (8, VarType::BitVec8, kind),
-            _ => unimplemented!(),
+            (_, U128) | (_, I128) => (16, VarType::BitVec8, kind),
+            (_, F32) => (4, VarType::BitVec8, kind),
+            (_, F64) => (8, VarType::BitVec8, kind),
+            _ => {
+                let msg = format!("abstract binary op {:?} on {:?}", bin_op, kind);
+                return Err(EvalError::Unimplemented(msg));
+            }
         };
 
         for idx in 0..num_bytes {
@@ -146,26 +589,105 @@ impl ConstraintContext {
 
         self.push_constraint(constraint);
 
-        primval
+        Ok(primval)
+    }
+
+    /// Creates a fresh abstract byte `X` and adds constraints that `X` is 1 if
+    /// the bool `val` holds and 0 otherwise. Returns `X`, which can be read as an
+    /// integer of any width.
+    pub fn add_bool_to_int_constraint<'tcx>(&mut self, val: PrimVal) -> EvalResult<'tcx, PrimVal> {
+        let mut buffer = [SByte::Concrete(0); 16];
+        buffer[0] = SByte::Abstract(self.allocate_abstract_var(VarType::BitVec8, VarOrigin::Inner));
+        let primval = PrimVal::Abstract(buffer);
+
+        self.push_constraint(Constraint::new_compare(
+            mir::BinOp::Le, PrimValKind::U8, primval, PrimVal::Bytes(1)));
+        let is_one = self.add_binop_constraint(
+            mir::BinOp::Eq, primval, PrimVal::Bytes(1), PrimValKind::U8)?;
+        self.push_constraint(Constraint::new_compare(
+            mir::BinOp::Eq, PrimValKind::Bool, is_one, val));
+
+        Ok(primval)
+    }
+
+    /// Creates a fresh abstract bool `X` and adds a constraint that `X` holds
+    /// exactly when `rhs_operand1 bin_op rhs_operand2` overflows. Returns `X`.
+    pub fn add_overflow_constraint<'tcx>(
+        &mut self,
+        bin_op: mir::BinOp,
+        rhs_operand1: PrimVal,
+        rhs_operand2: PrimVal,
+        kind: PrimValKind) -> EvalResult<'tcx, PrimVal> {
+
+        match bin_op {
+            mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul if kind.is_int() => (),
+            _ => {
+                let msg = format!("overflow check of abstract {:?} on {:?}", bin_op, kind);
+                return Err(EvalError::Unimplemented(msg));
+            }
+        }
+
+        let mut buffer = [SByte::Concrete(0); 16];
+        buffer[0] = SByte::Abstract(self.allocate_abstract_var(VarType::Bool, VarOrigin::Inner));
+        let primval = PrimVal::Abstract(buffer);
+
+        self.push_constraint(Constraint::Overflow {
+            operator: bin_op, kind, rhs_operand1, rhs_operand2, lhs: primval,
+        });
+
+        Ok(primval)
+    }
+
+    /// Creates a fresh abstract PrimVal `X` of `kind` and adds a constraint that
+    /// `X` is the bit count `op` of `val`. Returns `X`.
+    pub fn add_bit_count_constraint<'tcx>(
+        &mut self,
+        op: BitCount,
+        val: PrimVal,
+        kind: PrimValKind) -> EvalResult<'tcx, PrimVal> {
+
+        if !kind.is_int() {
+            let msg = format!("abstract bit count {:?} of {:?}", op, kind);
+            return Err(EvalError::Unimplemented(msg));
+        }
+
+        let mut buffer = [SByte::Concrete(0); 16];
+        for idx in 0..kind.num_bytes() {
+            buffer[idx] = SByte::Abstract(self.allocate_abstract_var(VarType::BitVec8, VarOrigin::Inner));
+        }
+
+        let primval = PrimVal::Abstract(buffer);
+        self.push_constraint(Constraint::BitCount { op, kind, operand: val, lhs: primval });
+
+        Ok(primval)
     }
 
     /// Creates a fresh abstract PrimVal `X` and adds a constraint
     /// `X == unop right`. Returns `X`.
-    pub fn add_unop_constraint(
+    pub fn add_unop_constraint<'tcx>(
         &mut self,
         un_op: mir::UnOp,
         val: PrimVal,
-        kind: PrimValKind) -> PrimVal {
+        kind: PrimValKind) -> EvalResult<'tcx, PrimVal> {
 
         use value::PrimValKind::*;
 
         let (num_bytes, var_type) = match kind {
             Bool => (1, VarType::Bool),
             U8 | I8 => (1, VarType::BitVec8),
-            _ => unimplemented!(),
+            U16 | I16 => (2, VarType::BitVec8),
+            U32 | I32 => (4, VarType::BitVec8),
+            U64 | I64 => (8, VarType::BitVec8),
+            U128 | I128 => (16, VarType::BitVec8),
+            F32 => (4, VarType::BitVec8),
+            F64 => (8, VarType::BitVec8),
+            _ => {
+                let msg = format!("abstract unary op {:?} on {:?}", un_op, kind);
+                return Err(EvalError::Unimplemented(msg));
+            }
         };
 
-        let mut buffer = [SByte::Concrete(0); 8];
+        let mut buffer = [SByte::Concrete(0); 16];
         for idx in 0..num_bytes {
             buffer[idx] = SByte::Abstract(self.allocate_abstract_var(var_type, VarOrigin::Inner));
         }
@@ -175,7 +697,26 @@ impl ConstraintContext {
 
         self.push_constraint(constraint);
 
-        primval
+        Ok(primval)
+    }
+
+    /// Creates a fresh abstract PrimVal `X` of kind `to` and adds a constraint
+    /// `X == val as to`, where `val` has kind `from`. Returns `X`.
+    pub fn add_float_cast_constraint<'tcx>(
+        &mut self,
+        val: PrimVal,
+        from: PrimValKind,
+        to: PrimValKind) -> EvalResult<'tcx, PrimVal> {
+
+        let mut buffer = [SByte::Concrete(0); 16];
+        for idx in 0..to.num_bytes() {
+            buffer[idx] = SByte::Abstract(self.allocate_abstract_var(VarType::BitVec8, VarOrigin::Inner));
+        }
+
+        let primval = PrimVal::Abstract(buffer);
+        self.push_constraint(Constraint::FloatCast { from, to, operand: val, lhs: primval });
+
+        Ok(primval)
     }
 
     pub fn new_array(&mut self) -> AbstractVariable {
@@ -209,72 +750,134 @@ impl ConstraintContext {
         value
     }
 
-    pub fn get_satisfying_values(&self) -> Vec<u8> {
-        let cfg = z3::Config::new();
-        let ctx = z3::Context::new(&cfg);
-        let solver = z3::Solver::new(&ctx);
-
-        let mut consts = Vec::new();
-
-        let mut result_consts = Vec::new();
-
-        for (idx, v) in self.variables.iter().enumerate() {
-            let (var_type, var_origin) = *v;
-            match var_type {
-                VarType::Bool => {
-                    consts.push(ctx.numbered_bool_const(idx as u32));
-                }
-                VarType::BitVec8 => {
-                    consts.push(ctx.numbered_bitvector_const(idx as u32, 8));
-                    if let VarOrigin::StdIn = var_origin {
-                        result_consts.push(ctx.numbered_bitvector_const(idx as u32, 8));
-                    }
-                }
-                VarType::Array => {
-                    consts.push(
-                        ::z3::Ast::new_const(
-                            &::z3::Symbol::from_int(&ctx, idx as u32),
-                            &ctx.array_sort(
-                                &ctx.bitvector_sort(64),
-                                &ctx.bitvector_sort(8))));
+    pub fn get_satisfying_values<'tcx>(&self) -> EvalResult<'tcx, Vec<u8>> {
+        let all = vec![true; self.constraints.len()];
+        let input = self.solver.borrow_mut().query(self, &all, &[], |ctx, model| {
+            let mut result = Vec::new();
+            for (idx, &(var_type, var_origin)) in self.variables.iter().enumerate() {
+                if let (VarType::BitVec8, VarOrigin::StdIn) = (var_type, var_origin) {
+                    let ast = ctx.numbered_bitvector_const(idx as u32, 8);
+                    result.push(model.eval(&ast).unwrap().as_u64().unwrap() as u8);
                 }
             }
+            Ok(result)
+        })?;
+        input.ok_or_else(unsatisfiable_path)
+    }
 
-        }
+    /// Finds a concrete value for `val` that is consistent with the current
+    /// constraints together with `constraints`.
+    pub fn get_satisfying_value<'tcx>(
+        &self,
+        constraints: &[Constraint],
+        val: PrimVal,
+        kind: PrimValKind)
+        -> EvalResult<'tcx, u64>
+    {
+        let all = vec![true; self.constraints.len()];
+        let value = self.solver.borrow_mut().query(self, &all, constraints, |ctx, model| {
+            let ast = self.primval_to_ast(ctx, val, kind)?;
+            Ok(model.eval(&ast).unwrap().as_u64().unwrap())
+        })?;
+        value.ok_or_else(unsatisfiable_path)
+    }
 
-        for c in &self.constraints {
-            solver.assert(&self.constraint_to_ast(&ctx, *c));
+    pub fn is_feasible_with<'tcx>(
+        &self,
+        constraints: &[Constraint])
+        -> EvalResult<'tcx, bool>
+    {
+        // Assuming that the current constraints are satisfiable, only those
+        // that share variables with the new ones can make them unsatisfiable.
+        let needed = self.relevant(constraints);
+        let mut ids = Vec::new();
+        let mut relevant = Vec::new();
+        for ((&id, c), &keep) in self.ids.iter().zip(self.constraints.iter()).zip(needed.iter()) {
+            if keep {
+                ids.push(id);
+                relevant.push(*c);
+            }
         }
+        let key = (ids, constraints.to_vec());
 
-        assert!(solver.check());
-        let model = solver.get_model();
+        let mut solver = self.solver.borrow_mut();
+        if let Some(&feasible) = solver.cache.results.get(&key) {
+            return Ok(feasible);
+        }
+        if solver.cache.has_model_for(relevant.iter().chain(constraints.iter())) {
+            solver.cache.insert_result(key, true);
+            return Ok(true);
+        }
 
-        let mut result = Vec::new();
-        for rc in result_consts {
-            result.push(model.eval(&rc).unwrap().as_u64().unwrap() as u8);
+        trace!("is_feasible_with: sending {} of {} constraints", relevant.len(), self.constraints.len());
+        let vars = self.bitvector_variables(relevant.iter().chain(constraints.iter()));
+        let model = solver.query(self, &needed, constraints, |ctx, model| {
+            Ok(vars.map(|vars| self.assignment_from_model(ctx, model, &vars)))
+        })?;
+        let feasible = model.is_some();
+        if let Some(Some(assignment)) = model {
+            solver.cache.insert_model(assignment);
         }
+        solver.cache.insert_result(key, feasible);
+        Ok(feasible)
+    }
 
-        result
+    /// The variables that occur in `constraints`, or `None` if one of them is
+    /// an array, which cached models do not cover.
+    fn bitvector_variables<'c, I>(&self, constraints: I) -> Option<Vec<u32>>
+        where I: Iterator<Item = &'c Constraint>
+    {
+        let mut vars = HashSet::new();
+        for c in constraints {
+            for v in c.variables() {
+                if let VarType::Array = self.variables[v.0 as usize].0 {
+                    return None;
+                }
+                vars.insert(v.0);
+            }
+        }
+        Some(vars.into_iter().collect())
     }
 
-    pub fn is_feasible_with(
+    fn assignment_from_model<'a>(
         &self,
-        constraints: &[Constraint])
-        -> bool
+        ctx: &'a z3::Context,
+        model: &z3::Model<'a>,
+        vars: &[u32])
+        -> Assignment
     {
-        let cfg = z3::Config::new();
-        let ctx = z3::Context::new(&cfg);
-        let solver = z3::Solver::new(&ctx);
-
-        let mut all_constraints: Vec<Constraint> = Vec::new();
-        all_constraints.extend(self.constraints.iter().clone());
-        all_constraints.extend(constraints.iter().clone());
+        let mut assignment = HashMap::new();
+        for &v in vars {
+            let ast = match self.variables[v as usize].0 {
+                VarType::Bool => {
+                    ctx.numbered_bool_const(v).ite(
+                        &z3::Ast::bv_from_u64(ctx, 1, 1),
+                        &z3::Ast::bv_from_u64(ctx, 0, 1))
+                }
+                _ => ctx.numbered_bitvector_const(v, 8),
+            };
+            if let Some(value) = model.eval(&ast).and_then(|value| value.as_u64()) {
+                assignment.insert(v, value);
+            }
+        }
+        assignment
+    }
 
-        for c in all_constraints {
-            solver.assert(&self.constraint_to_ast(&ctx, c));
+    /// For each current constraint, whether it is connected to `constraints`
+    /// through shared variables. Constraints without any variables are always
+    /// connected.
+    fn relevant(&self, constraints: &[Constraint]) -> Vec<bool> {
+        let mut roots = HashSet::new();
+        for c in constraints {
+            for v in c.variables() {
+                roots.insert(self.var_sets.find(v));
+            }
         }
 
-        solver.check()
+        self.first_vars.iter().map(|first_var| match *first_var {
+            Some(v) => roots.contains(&self.var_sets.find(v)),
+            None => true,
+        }).collect()
     }
 
     fn sbyte_to_ast<'a>(
@@ -313,63 +916,66 @@ impl ConstraintContext {
         }
     }
 
-    fn constraint_to_ast<'a>(
+    fn constraint_to_ast<'a, 'tcx>(
         &self,
         ctx: &'a z3::Context,
         constraint: Constraint)
-        -> z3::Ast<'a>
+        -> EvalResult<'tcx, z3::Ast<'a>>
     {
         match constraint {
             Constraint::Binop { operator, kind, lhs, rhs_operand1,
                                 rhs_operand2, lhs_kind } => {
-                self.primval_to_ast(&ctx, lhs, lhs_kind)._eq(
+                if let Some(format) = float::Format::of(lhs_kind) {
+                    let widened = |val| {
+                        self.primval_to_ast(ctx, val, kind).map(|ast| float::widen(ctx, ast, format.bits()))
+                    };
+                    let result = float::binop_to_ast(
+                        ctx, format, operator, &widened(rhs_operand1)?, &widened(rhs_operand2)?)?;
+                    return Ok(widened(lhs)?._eq(&result));
+                }
+                Ok(self.primval_to_ast(&ctx, lhs, lhs_kind)?._eq(
                     &self.mir_binop_to_ast(
                         &ctx,
                         operator,
-                        self.primval_to_ast(&ctx, rhs_operand1, kind),
-                        self.primval_to_ast(&ctx, rhs_operand2, kind),
-                        kind))
+                        self.primval_to_ast(&ctx, rhs_operand1, kind)?,
+                        self.primval_to_ast(&ctx, rhs_operand2, kind)?,
+                        kind)?))
             }
             Constraint::Unop { operator, kind, lhs, operand, .. } => {
-                self.primval_to_ast(&ctx, lhs, kind)._eq(
+                Ok(self.primval_to_ast(&ctx, lhs, kind)?._eq(
                     &self.mir_unop_to_ast(
                         &ctx,
                         operator,
-                        self.primval_to_ast(&ctx, operand, kind)))
+                        self.primval_to_ast(&ctx, operand, kind)?,
+                        kind)?))
             }
 
             Constraint::Compare { op, lhs, rhs, kind, .. } => {
-                // TODO(cleanup) this duplicates some functionality of mir_binop_to_ast().
-                // Can we consolidate?
-                match op {
-                    mir::BinOp::Eq => {
-                        self.primval_to_ast(&ctx, lhs, kind)._eq(
-                            &self.primval_to_ast(&ctx, rhs, kind))
-                    }
-                    mir::BinOp::Ne => {
-                        self.primval_to_ast(&ctx, lhs, kind)._eq(
-                            &self.primval_to_ast(&ctx, rhs, kind)).not()
-                    }
-                    mir::BinOp::Gt => {
-                        if kind.is_signed_int() {
-                            unimplemented!()
-                        }
-                        self.primval_to_ast(&ctx, lhs, kind).bvugt(
-                            &self.primval_to_ast(&ctx, rhs, kind))
-                    }
+                self.mir_binop_to_ast(
+                    &ctx,
+                    op,
+                    self.primval_to_ast(&ctx, lhs, kind)?,
+                    self.primval_to_ast(&ctx, rhs, kind)?,
+                    kind)
+            }
 
-                    mir::BinOp::Lt => {
-                        if kind.is_signed_int() {
-                            unimplemented!()
-                        }
-                        self.primval_to_ast(ctx, lhs, kind).bvult(
-                            &self.primval_to_ast(&ctx, rhs, kind))
-                    }
+            Constraint::Overflow { operator, kind, rhs_operand1, rhs_operand2, lhs } => {
+                Ok(self.primval_to_ast(&ctx, lhs, PrimValKind::Bool)?._eq(
+                    &self.overflow_to_ast(
+                        &ctx,
+                        operator,
+                        self.primval_to_ast(&ctx, rhs_operand1, kind)?,
+                        self.primval_to_ast(&ctx, rhs_operand2, kind)?,
+                        kind)))
+            }
 
-                    _ => {
-                        unimplemented!()
-                    }
-                }
+            Constraint::BitCount { op, kind, operand, lhs } => {
+                Ok(self.primval_to_ast(&ctx, lhs, kind)?._eq(
+                    &self.bit_count_to_ast(
+                        &ctx,
+                        op,
+                        self.primval_to_ast(&ctx, operand, kind)?,
+                        kind)?))
             }
 
             Constraint::ArrayElement { array, index, value, } => {
@@ -379,67 +985,102 @@ impl ConstraintContext {
                         &ctx.bitvector_sort(64),
                         &ctx.bitvector_sort(8)));
 
-                c.select(&self.primval_to_ast(ctx, index, PrimValKind::U64))._eq(
-                    &self.sbyte_to_ast(ctx, value))
+                Ok(c.select(&self.primval_to_ast(ctx, index, PrimValKind::U64)?)._eq(
+                    &self.sbyte_to_ast(ctx, value)))
+            }
+
+            Constraint::FloatCast { from, to, operand, lhs } => {
+                let (from_bits, to_bits) = (from.num_bytes() as u32 * 8, to.num_bytes() as u32 * 8);
+                let operand = float::widen(ctx, self.primval_to_ast(ctx, operand, from)?, from_bits);
+                let result = match (float::Format::of(from), float::Format::of(to)) {
+                    (Some(from_format), Some(to_format)) => {
+                        float::float_to_float(ctx, from_format, to_format, &operand)
+                    }
+                    (Some(format), None) => {
+                        float::float_to_int(ctx, format, to.is_signed_int(), to_bits, &operand)
+                    }
+                    (None, Some(format)) => {
+                        float::int_to_float(ctx, from.is_signed_int(), from_bits, format, &operand)
+                    }
+                    (None, None) => bug!("float cast from {:?} to {:?}", from, to),
+                };
+                let lhs = float::widen(ctx, self.primval_to_ast(ctx, lhs, to)?, to_bits);
+                Ok(lhs._eq(&result))
             }
         }
     }
 
-    fn primval_to_ast<'a>(
+    fn primval_to_ast<'a, 'tcx>(
         &self,
         ctx: &'a z3::Context,
         primval: PrimVal,
         kind: PrimValKind)
-        -> z3::Ast<'a>
+        -> EvalResult<'tcx, z3::Ast<'a>>
     {
         match primval {
             PrimVal::Undef => {
-                unimplemented!()
+                Err(EvalError::ReadUndefBytes)
             }
             PrimVal::Ptr(_) => {
-                unimplemented!()
+                Err(EvalError::Unimplemented("pointer in abstract constraint".to_string()))
             }
             PrimVal::Abstract(sbytes) => {
                 if let PrimValKind::Bool = kind {
                     match sbytes[0] {
                         SByte::Abstract(b) => {
-                            ctx.numbered_bool_const(b.0)
+                            Ok(ctx.numbered_bool_const(b.0))
                         }
-                        SByte::Concrete(_b) => {
-                            unimplemented!()
+                        SByte::Concrete(b) => {
+                            Ok(z3::Ast::from_bool(&ctx, b != 0))
                         }
                     }
                 } else {
                     let num_bytes = kind.num_bytes();
-                    self.sbyte_slice_to_ast(ctx, &sbytes[..num_bytes])
+                    Ok(self.sbyte_slice_to_ast(ctx, &sbytes[..num_bytes]))
                 }
             }
             PrimVal::Bytes(v) => {
                 match kind {
-                    PrimValKind::Bool => z3::Ast::from_bool(&ctx, v != 0),
-                    PrimValKind::U8 | PrimValKind::I8 => z3::Ast::bv_from_u64(&ctx, v as u64, 8),
-                    PrimValKind::U16 | PrimValKind::I16 => z3::Ast::bv_from_u64(&ctx, v as u64, 16),
-                    PrimValKind::U32 | PrimValKind::I32 => z3::Ast::bv_from_u64(&ctx, v as u64, 32),
-                    PrimValKind::U64 | PrimValKind::I64 => z3::Ast::bv_from_u64(&ctx, v as u64, 64),
+                    PrimValKind::Bool => Ok(z3::Ast::from_bool(&ctx, v != 0)),
+                    PrimValKind::U8 | PrimValKind::I8 => Ok(z3::Ast::bv_from_u64(&ctx, v as u64, 8)),
+                    PrimValKind::U16 | PrimValKind::I16 => Ok(z3::Ast::bv_from_u64(&ctx, v as u64, 16)),
+                    PrimValKind::U32 | PrimValKind::I32 |
+                    PrimValKind::Char => Ok(z3::Ast::bv_from_u64(&ctx, v as u64, 32)),
+                    PrimValKind::U64 | PrimValKind::I64 => Ok(z3::Ast::bv_from_u64(&ctx, v as u64, 64)),
+                    PrimValKind::F32 => Ok(z3::Ast::bv_from_u64(&ctx, v as u64, 32)),
+                    PrimValKind::F64 => Ok(z3::Ast::bv_from_u64(&ctx, v as u64, 64)),
+                    PrimValKind::U128 | PrimValKind::I128 => {
+                        let high = z3::Ast::bv_from_u64(&ctx, (v >> 64) as u64, 64);
+                        let low = z3::Ast::bv_from_u64(&ctx, v as u64, 64);
+                        Ok(high.concat(&low))
+                    }
 
                     _ => {
-                        unimplemented!()
+                        let msg = format!("{:?} value in abstract constraint", kind);
+                        Err(EvalError::Unimplemented(msg))
                     }
                 }
             }
         }
     }
 
-    fn mir_binop_to_ast<'a>(
+    fn mir_binop_to_ast<'a, 'tcx>(
         &self,
-        _ctx: &'a z3::Context,
+        ctx: &'a z3::Context,
         operator: mir::BinOp,
         left: z3::Ast<'a>,
         right: z3::Ast<'a>,
         kind: PrimValKind)
-        -> z3::Ast<'a>
+        -> EvalResult<'tcx, z3::Ast<'a>>
     {
-        match (operator, kind) {
+        match kind {
+            PrimValKind::F32 | PrimValKind::F64 => {
+                return self.float_compare_to_ast(ctx, operator, left, right, kind);
+            }
+            _ => {}
+        }
+
+        Ok(match (operator, kind) {
             (mir::BinOp::Eq, _) => left._eq(&right),
             (mir::BinOp::Ne, _) => left._eq(&right).not(),
 
@@ -480,24 +1121,325 @@ impl ConstraintContext {
             (mir::BinOp::Rem, _) => left.bvurem(&right),
 
             _ => {
-                println!("{:?}", operator);
-                unimplemented!()
+                let msg = format!("abstract binary op {:?} on {:?}", operator, kind);
+                return Err(EvalError::Unimplemented(msg));
+            }
+        })
+    }
+
+    /// Translates a comparison of two floats. seer-z3 does not expose z3's
+    /// floating-point theory, so this works directly on the IEEE 754 bits.
+    fn float_compare_to_ast<'a, 'tcx>(
+        &self,
+        ctx: &'a z3::Context,
+        operator: mir::BinOp,
+        left: z3::Ast<'a>,
+        right: z3::Ast<'a>,
+        kind: PrimValKind)
+        -> EvalResult<'tcx, z3::Ast<'a>>
+    {
+        let (bits, exponent_mask, mantissa_mask) = match kind {
+            PrimValKind::F32 => (32, 0x7f80_0000, 0x007f_ffff),
+            PrimValKind::F64 => (64, 0x7ff0_0000_0000_0000, 0x000f_ffff_ffff_ffff),
+            _ => bug!("float comparison on {:?}", kind),
+        };
+        let sign = z3::Ast::bv_from_u64(ctx, 1 << (bits - 1), bits);
+        let exponent = z3::Ast::bv_from_u64(ctx, exponent_mask, bits);
+        let mantissa = z3::Ast::bv_from_u64(ctx, mantissa_mask, bits);
+        let zero = z3::Ast::bv_from_u64(ctx, 0, bits);
+
+        let is_nan = |x: &z3::Ast<'a>| {
+            x.bvand(&exponent)._eq(&exponent).and(&[&x.bvand(&mantissa)._eq(&zero).not()])
+        };
+        let is_zero = |x: &z3::Ast<'a>| x.bvand(&sign.bvnot())._eq(&zero);
+
+        // Maps a float that is not NaN to an unsigned integer of the same order,
+        // except that -0.0 comes right before +0.0.
+        let key = |x: &z3::Ast<'a>| x.bvand(&sign)._eq(&zero).ite(&x.bvor(&sign), &x.bvnot());
+
+        let ordered = is_nan(&left).or(&[&is_nan(&right)]).not();
+        let both_zero = is_zero(&left).and(&[&is_zero(&right)]);
+        let eq = ordered.and(&[&both_zero.or(&[&left._eq(&right)])]);
+        let lt = ordered.and(&[&both_zero.not(), &key(&left).bvult(&key(&right))]);
+        let gt = ordered.and(&[&both_zero.not(), &key(&left).bvugt(&key(&right))]);
+
+        Ok(match operator {
+            mir::BinOp::Eq => eq,
+            mir::BinOp::Ne => eq.not(),
+            mir::BinOp::Lt => lt,
+            mir::BinOp::Le => lt.or(&[&eq]),
+            mir::BinOp::Gt => gt,
+            mir::BinOp::Ge => gt.or(&[&eq]),
+            _ => {
+                let msg = format!("abstract floating-point {:?}", operator);
+                return Err(EvalError::Unimplemented(msg));
+            }
+        })
+    }
+
+    /// Whether `left operator right` overflows, for `Add`, `Sub` and `Mul` on
+    /// integers. Multiplication is checked in twice the width.
+    fn overflow_to_ast<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        operator: mir::BinOp,
+        left: z3::Ast<'a>,
+        right: z3::Ast<'a>,
+        kind: PrimValKind)
+        -> z3::Ast<'a>
+    {
+        let bits = (kind.num_bytes() * 8) as u32;
+        let signed = kind.is_signed_int();
+        let zero = z3::Ast::bv_from_u64(ctx, 0, bits);
+        let negative = |x: &z3::Ast<'a>| x.bvslt(&zero);
+
+        match (operator, signed) {
+            (mir::BinOp::Add, false) => left.bvadd(&right).bvult(&left),
+            (mir::BinOp::Sub, false) => left.bvult(&right),
+
+            // The operands have the same sign and the result has the other one.
+            (mir::BinOp::Add, true) => {
+                let result = left.bvadd(&right);
+                negative(&left)._eq(&negative(&right))
+                    .and(&[&negative(&result)._eq(&negative(&left)).not()])
+            }
+
+            // The operands have different signs and the result has the sign of `right`.
+            (mir::BinOp::Sub, true) => {
+                let result = left.bvsub(&right);
+                negative(&left)._eq(&negative(&right)).not()
+                    .and(&[&negative(&result)._eq(&negative(&left)).not()])
+            }
+
+            (mir::BinOp::Mul, false) => {
+                let max = zero.bvnot();
+                let product = zero.concat(&left).bvmul(&zero.concat(&right));
+                product.bvugt(&zero.concat(&max))
+            }
+
+            (mir::BinOp::Mul, true) => {
+                let ones = zero.bvnot();
+                let extend = |x: &z3::Ast<'a>| negative(x).ite(&ones.concat(x), &zero.concat(x));
+                let min = z3::Ast::bv_from_u64(ctx, 1, bits)
+                    .bvshl(&z3::Ast::bv_from_u64(ctx, (bits - 1) as u64, bits));
+                let max = min.bvnot();
+                let product = extend(&left).bvmul(&extend(&right));
+                product.bvslt(&extend(&min)).or(&[&product.bvsgt(&extend(&max))])
             }
+
+            _ => bug!("overflow check of {:?}", operator),
         }
     }
 
-    fn mir_unop_to_ast<'a>(
+    /// Counts the bits of `val` as `op` says. Leading and trailing zeros are
+    /// found by testing the bits one at a time, starting from the far end, so that
+    /// the last bit that is set decides the result.
+    fn bit_count_to_ast<'a, 'tcx>(
         &self,
-        _ctx: &'a z3::Context,
+        ctx: &'a z3::Context,
+        op: BitCount,
+        val: z3::Ast<'a>,
+        kind: PrimValKind)
+        -> EvalResult<'tcx, z3::Ast<'a>>
+    {
+        let bits = kind.num_bytes() as u128 * 8;
+        let constant = |n: u128| self.primval_to_ast(ctx, PrimVal::Bytes(n), kind);
+        let one = constant(1)?;
+        let bit = |i: u128| -> EvalResult<'tcx, z3::Ast<'a>> {
+            Ok(val.bvlshr(&constant(i)?).bvand(&one))
+        };
+
+        let mut result = match op {
+            BitCount::Ones => constant(0)?,
+            BitCount::LeadingZeros | BitCount::TrailingZeros => constant(bits)?,
+        };
+        for i in 0..bits {
+            result = match op {
+                BitCount::Ones => result.bvadd(&bit(i)?),
+                BitCount::LeadingZeros => bit(i)?._eq(&one).ite(&constant(bits - 1 - i)?, &result),
+                BitCount::TrailingZeros => {
+                    let i = bits - 1 - i;
+                    bit(i)?._eq(&one).ite(&constant(i)?, &result)
+                }
+            };
+        }
+        Ok(result)
+    }
+
+    fn mir_unop_to_ast<'a, 'tcx>(
+        &self,
+        ctx: &'a z3::Context,
         operator: mir::UnOp,
-        val: z3::Ast<'a>,)
-        -> z3::Ast<'a>
+        val: z3::Ast<'a>,
+        kind: PrimValKind)
+        -> EvalResult<'tcx, z3::Ast<'a>>
     {
-        match operator {
-            mir::UnOp::Not => val.not(),
-            mir::UnOp::Neg => {
-                unimplemented!()
+        Ok(match (operator, kind) {
+            (mir::UnOp::Not, PrimValKind::Bool) => val.not(),
+            (mir::UnOp::Not, PrimValKind::F32) |
+            (mir::UnOp::Not, PrimValKind::F64) => bug!("bitwise not of a float"),
+            (mir::UnOp::Not, _) => val.bvnot(),
+            (mir::UnOp::Neg, PrimValKind::Bool) => bug!("negation of a bool"),
+
+            // Flip the sign bit.
+            (mir::UnOp::Neg, PrimValKind::F32) => val.bvxor(&z3::Ast::bv_from_u64(ctx, 1 << 31, 32)),
+            (mir::UnOp::Neg, PrimValKind::F64) => val.bvxor(&z3::Ast::bv_from_u64(ctx, 1 << 63, 64)),
+            (mir::UnOp::Neg, _) => val.bvneg(),
+        })
+    }
+}
+
+/// Every path is checked for feasibility before it is taken, so its constraints
+/// should always have a model.
+fn unsatisfiable_path<'tcx>() -> EvalError<'tcx> {
+    EvalError::Unimplemented("path constraints are unsatisfiable".to_string())
+}
+
+/// The width in bits of values of `kind`, if concrete evaluation supports it.
+fn eval_width(kind: PrimValKind) -> Option<u32> {
+    use value::PrimValKind::*;
+    match kind {
+        Bool => Some(1),
+        I8 | U8 => Some(8),
+        I16 | U16 => Some(16),
+        I32 | U32 | Char => Some(32),
+        I64 | U64 => Some(64),
+        _ => None,
+    }
+}
+
+fn width_mask(bits: u32) -> u64 {
+    if bits == 64 { !0 } else { (1 << bits) - 1 }
+}
+
+fn sign_extend(v: u64, bits: u32) -> i64 {
+    ((v << (64 - bits)) as i64) >> (64 - bits)
+}
+
+/// The value of `val` under `assignment`, or `None` if it cannot be determined.
+fn eval_primval(assignment: &Assignment, val: PrimVal, kind: PrimValKind) -> Option<u64> {
+    let bits = eval_width(kind)?;
+    match val {
+        PrimVal::Bytes(v) if kind == PrimValKind::Bool => Some((v != 0) as u64),
+        PrimVal::Bytes(v) => Some(v as u64 & width_mask(bits)),
+        PrimVal::Abstract(sbytes) if kind == PrimValKind::Bool => match sbytes[0] {
+            SByte::Concrete(b) => Some((b != 0) as u64),
+            SByte::Abstract(v) => assignment.get(&v.0).cloned(),
+        },
+        PrimVal::Abstract(sbytes) => {
+            let mut result = 0;
+            for (idx, sbyte) in sbytes[..(bits / 8) as usize].iter().enumerate() {
+                let byte = match *sbyte {
+                    SByte::Concrete(b) => b as u64,
+                    SByte::Abstract(v) => *assignment.get(&v.0)?,
+                };
+                result |= byte << (8 * idx);
             }
+            Some(result)
+        }
+        PrimVal::Ptr(_) | PrimVal::Undef => None,
+    }
+}
+
+/// Evaluates `left operator right` like `ConstraintContext::mir_binop_to_ast()`
+/// does, or returns `None` where that is not straightforward.
+fn eval_binop(operator: mir::BinOp, kind: PrimValKind, left: u64, right: u64) -> Option<u64> {
+    use rustc::mir::BinOp::*;
+    let bits = eval_width(kind)?;
+    let mask = width_mask(bits);
+    let signed = kind.is_signed_int();
+    let (sl, sr) = (sign_extend(left, bits), sign_extend(right, bits));
+    Some(match operator {
+        Eq => (left == right) as u64,
+        Ne => (left != right) as u64,
+        Lt => if signed { (sl < sr) as u64 } else { (left < right) as u64 },
+        Le => if signed { (sl <= sr) as u64 } else { (left <= right) as u64 },
+        Gt => if signed { (sl > sr) as u64 } else { (left > right) as u64 },
+        Ge => if signed { (sl >= sr) as u64 } else { (left >= right) as u64 },
+        Add => left.wrapping_add(right) & mask,
+        Sub => left.wrapping_sub(right) & mask,
+        Mul => left.wrapping_mul(right) & mask,
+        BitXor => left ^ right,
+        BitAnd => left & right,
+        BitOr => left | right,
+        Shl if right >= bits as u64 => 0,
+        Shl => (left << right) & mask,
+        Shr if signed && right >= bits as u64 => if sl < 0 { mask } else { 0 },
+        Shr if signed => (sl >> right) as u64 & mask,
+        Shr if right >= bits as u64 => 0,
+        Shr => left >> right,
+
+        // z3 gives division by zero a meaning of its own; leave that to z3.
+        Div | Rem if right == 0 => return None,
+        Div if signed => sl.wrapping_div(sr) as u64 & mask,
+        Div => left / right,
+        Rem if signed => sl.wrapping_rem(sr) as u64 & mask,
+        Rem => left % right,
+        Offset => return None,
+    })
+}
+
+/// Whether `constraint` holds under `assignment`, or `None` if that cannot be
+/// determined.
+fn eval_constraint(assignment: &Assignment, constraint: &Constraint) -> Option<bool> {
+    match *constraint {
+        Constraint::Binop { operator, kind, rhs_operand1, rhs_operand2, lhs, lhs_kind } => {
+            let left = eval_primval(assignment, rhs_operand1, kind)?;
+            let right = eval_primval(assignment, rhs_operand2, kind)?;
+            let result = eval_binop(operator, kind, left, right)?;
+            Some(eval_primval(assignment, lhs, lhs_kind)? == result)
+        }
+        Constraint::Unop { operator, kind, operand, lhs } => {
+            let mask = width_mask(eval_width(kind)?);
+            let operand = eval_primval(assignment, operand, kind)?;
+            let result = match (operator, kind) {
+                (mir::UnOp::Not, _) => !operand & mask,
+                (mir::UnOp::Neg, PrimValKind::Bool) => return None,
+                (mir::UnOp::Neg, _) => operand.wrapping_neg() & mask,
+            };
+            Some(eval_primval(assignment, lhs, kind)? == result)
+        }
+        Constraint::Compare { op, kind, lhs, rhs } => {
+            let left = eval_primval(assignment, lhs, kind)?;
+            let right = eval_primval(assignment, rhs, kind)?;
+            Some(eval_binop(op, kind, left, right)? != 0)
+        }
+        Constraint::Overflow { operator, kind, rhs_operand1, rhs_operand2, lhs } => {
+            let bits = eval_width(kind)?;
+            let left = eval_primval(assignment, rhs_operand1, kind)?;
+            let right = eval_primval(assignment, rhs_operand2, kind)?;
+            let result = if kind.is_signed_int() {
+                let (l, r) = (sign_extend(left, bits) as i128, sign_extend(right, bits) as i128);
+                let min = -(1i128 << (bits - 1));
+                let exact = match operator {
+                    mir::BinOp::Add => l + r,
+                    mir::BinOp::Sub => l - r,
+                    mir::BinOp::Mul => l * r,
+                    _ => return None,
+                };
+                exact < min || exact > -min - 1
+            } else {
+                let (l, r) = (left as u128, right as u128);
+                match operator {
+                    mir::BinOp::Add => l + r > width_mask(bits) as u128,
+                    mir::BinOp::Sub => l < r,
+                    mir::BinOp::Mul => l * r > width_mask(bits) as u128,
+                    _ => return None,
+                }
+            };
+            Some((eval_primval(assignment, lhs, PrimValKind::Bool)? != 0) == result)
+        }
+        Constraint::BitCount { op, kind, operand, lhs } => {
+            let bits = eval_width(kind)?;
+            let operand = eval_primval(assignment, operand, kind)?;
+            let result = match op {
+                BitCount::Ones => operand.count_ones(),
+                BitCount::LeadingZeros => operand.leading_zeros() - (64 - bits),
+                BitCount::TrailingZeros => ::std::cmp::min(operand.trailing_zeros(), bits),
+            };
+            Some(eval_primval(assignment, lhs, kind)? == result as u64)
         }
+        Constraint::ArrayElement { .. } |
+        Constraint::FloatCast { .. } => None,
     }
 }
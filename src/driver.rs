@@ -6,10 +6,12 @@ use rustc_errors;
 use rustc::session::config::{self, Input, ErrorOutputType};
 use syntax;
 use syntax::ast::{MetaItemKind, NestedMetaItemKind, self};
+use std::cell::Cell;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std;
 
-struct SeerCompilerCalls(RustcDefaultCalls, ::ExecutionConfig);
+struct SeerCompilerCalls(RustcDefaultCalls, ::ExecutionConfig, Rc<Cell<::ExecutionStatus>>);
 
 impl<'a> CompilerCalls<'a> for SeerCompilerCalls {
     fn early_callback(
@@ -46,7 +48,7 @@ impl<'a> CompilerCalls<'a> for SeerCompilerCalls {
     fn build_controller(&mut self, sess: &Session, matches: &getopts::Matches) -> CompileController<'a> {
         let mut control = self.0.build_controller(sess, matches);
         control.after_hir_lowering.callback = Box::new(after_hir_lowering);
-        control.after_analysis.callback = after_analysis_run_main(self.1.clone());
+        control.after_analysis.callback = after_analysis_run_main(self.1.clone(), self.2.clone());
 
         if std::env::var("MIRI_HOST_TARGET") != Ok("yes".to_owned()) {
             // only fully compile targets on the host
@@ -61,7 +63,7 @@ fn after_hir_lowering(state: &mut CompileState) {
     state.session.plugin_attributes.borrow_mut().push(attr);
 }
 
-fn after_analysis_run_main<'a, 'tcx>(config: ::ExecutionConfig)
+fn after_analysis_run_main<'a, 'tcx>(config: ::ExecutionConfig, status: Rc<Cell<::ExecutionStatus>>)
     -> Box<Fn(&mut CompileState) + 'static>
 {
     Box::new(move |state: &mut CompileState| {
@@ -74,7 +76,7 @@ fn after_analysis_run_main<'a, 'tcx>(config: ::ExecutionConfig)
             let entry_def_id = tcx.hir.local_def_id(entry_node_id);
 
             let mut executor = ::executor::Executor::new(tcx, entry_def_id, limits, config.clone());
-            executor.run();
+            status.set(executor.run());
 
             state.session.abort_if_errors();
         } else {
@@ -103,6 +105,8 @@ fn resource_limits_from_attributes(state: &CompileState) -> ::ResourceLimits {
                             "memory_size" => limits.memory_size = extract_int(value) as u64,
                             "step_limit" => limits.step_limit = extract_int(value) as u64,
                             "stack_limit" => limits.stack_limit = extract_int(value) as usize,
+                            "symbolic_allocation_size" =>
+                                limits.symbolic_allocation_size = extract_int(value) as u64,
                             _ => state.session.span_err(item.span, "unknown miri attribute"),
                         }
                     } else {
@@ -131,7 +135,8 @@ fn find_sysroot() -> String {
     }
 }
 
-pub fn main_helper(mut args: Vec<String>, config: ::ExecutionConfig)
+/// Compiles the program that `args` name and explores it with `config`.
+pub fn main_helper(mut args: Vec<String>, config: ::ExecutionConfig) -> ::ExecutionStatus
 {
     let sysroot_flag = String::from("--sysroot");
     if !args.contains(&sysroot_flag) {
@@ -144,6 +149,8 @@ pub fn main_helper(mut args: Vec<String>, config: ::ExecutionConfig)
     // for auxilary builds in unit tests
     args.push("-Zalways-encode-mir".to_owned());
 
-    rustc_driver::run_compiler(&args, &mut SeerCompilerCalls(RustcDefaultCalls, config),
+    let status = Rc::new(Cell::new(::ExecutionStatus::Complete));
+    rustc_driver::run_compiler(&args, &mut SeerCompilerCalls(RustcDefaultCalls, config, status.clone()),
                                None, None);
+    status.get()
 }
@@ -31,6 +31,7 @@ pub enum EvalError<'tcx> {
     ArrayIndexOutOfBounds(Span, u64, u64),
     Math(Span, ConstMathErr),
     InvalidChar(u128),
+    Intrinsic(String),
     OutOfMemory {
         allocation_size: u64,
         memory_size: u64,
@@ -53,6 +54,7 @@ pub enum EvalError<'tcx> {
     Layout(layout::LayoutError<'tcx>),
     Unreachable,
     Panic,
+    SolverUnknown,
 }
 
 pub type EvalResult<'tcx, T = ()> = Result<T, EvalError<'tcx>>;
@@ -95,6 +97,8 @@ impl<'tcx> Error for EvalError<'tcx> {
                 "mir not found",
             EvalError::InvalidChar(..) =>
                 "tried to interpret an invalid 32-bit value as a char",
+            EvalError::Intrinsic(..) =>
+                "intrinsic failed",
             EvalError::OutOfMemory{..} =>
                 "could not allocate more memory",
             EvalError::ExecutionTimeLimitReached =>
@@ -127,6 +131,8 @@ impl<'tcx> Error for EvalError<'tcx> {
                 "entered unreachable code",
             EvalError::Panic =>
                 "the evaluated program panicked",
+            EvalError::SolverUnknown =>
+                "the solver could not decide whether the path is feasible",
         }
     }
 
@@ -144,7 +150,12 @@ impl<'tcx> fmt::Display for EvalError<'tcx> {
                             "memory access of {}..{} outside bounds of allocation {} which has size {}",
                             ptr_offset, ptr_offset + size, ptr.alloc_id, allocation_size)
                     }
-                    _ => unimplemented!(),
+                    PointerOffset::Abstract(_) => {
+                        write!(
+                            f,
+                            "memory access of {} bytes at an abstract offset outside bounds of allocation {} which has size {}",
+                            size, ptr.alloc_id, allocation_size)
+                    }
                 }
             },
             EvalError::NoMirFor(ref func) => write!(f, "no mir for `{}`", func),
@@ -156,6 +167,8 @@ impl<'tcx> fmt::Display for EvalError<'tcx> {
                 write!(f, "{:?} at {:?}", err, span),
             EvalError::InvalidChar(c) =>
                 write!(f, "tried to interpret an invalid 32-bit value as a char: {}", c),
+            EvalError::Intrinsic(ref msg) =>
+                write!(f, "{}", msg),
             EvalError::OutOfMemory { allocation_size, memory_size, memory_usage } =>
                 write!(f, "tried to allocate {} more bytes, but only {} bytes are free of the {} byte memory",
                        allocation_size, memory_size - memory_usage, memory_size),
@@ -171,7 +184,7 @@ impl<'tcx> fmt::Display for EvalError<'tcx> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, RustcEncodable, RustcDecodable)]
 pub enum StaticEvalError {
     FunctionPointerTyMismatch,
     NoMirFor(String),
@@ -196,6 +209,7 @@ pub enum StaticEvalError {
     ArrayIndexOutOfBounds(Span, u64, u64),
     Math(Span, ConstMathErr),
     InvalidChar(u128),
+    Intrinsic(String),
     OutOfMemory {
         allocation_size: u64,
         memory_size: u64,
@@ -218,6 +232,7 @@ pub enum StaticEvalError {
     Layout,
     Unreachable,
     Panic,
+    SolverUnknown,
 }
 
 impl <'tcx> From<EvalError<'tcx>> for StaticEvalError {
@@ -259,6 +274,8 @@ impl <'tcx> From<EvalError<'tcx>> for StaticEvalError {
                 StaticEvalError::NoMirFor(s.clone()),
             EvalError::InvalidChar(c) =>
                 StaticEvalError::InvalidChar(c),
+            EvalError::Intrinsic(ref msg) =>
+                StaticEvalError::Intrinsic(msg.clone()),
             EvalError::OutOfMemory { allocation_size, memory_size, memory_usage, } =>
                 StaticEvalError::OutOfMemory { allocation_size, memory_size, memory_usage },
             EvalError::ExecutionTimeLimitReached =>
@@ -291,6 +308,8 @@ impl <'tcx> From<EvalError<'tcx>> for StaticEvalError {
                 StaticEvalError::Unreachable,
             EvalError::Panic =>
                 StaticEvalError::Panic,
+            EvalError::SolverUnknown =>
+                StaticEvalError::SolverUnknown,
         }
     }
 }
@@ -13,6 +13,7 @@ use syntax::codemap::{self, DUMMY_SP, Span};
 use syntax::ast;
 use syntax::abi::Abi;
 
+use constraints::{Constraint, ConstraintContext};
 use error::{EvalError, EvalResult};
 use lvalue::{Global, GlobalId, Lvalue, LvalueExtra};
 use memory::{Memory, Pointer};
@@ -39,6 +40,22 @@ pub struct EvalContext<'a, 'tcx: 'a> {
     /// This prevents infinite loops and huge computations from freezing up const eval.
     /// Remove once halting problem is solved.
     pub(crate) steps_remaining: u64,
+
+    /// The largest number of bytes that an allocation of abstract size may get.
+    pub(crate) symbolic_allocation_size: u64,
+
+    /// For each branch point at which this path split off from others, the index
+    /// of the branch that it took.
+    pub(crate) branch_path: Vec<u32>,
+
+    /// Paths that split off this one during the current step because an abstract
+    /// value could violate a validity invariant. Each entry holds the constraints of
+    /// such a path and the error that it ends in.
+    pub(crate) side_errors: Vec<(ConstraintContext, EvalError<'tcx>)>,
+
+    /// Paths that split off this one during the current step, and that start over
+    /// with the current statement or terminator.
+    pub(crate) side_paths: Vec<EvalContext<'a, 'tcx>>,
 }
 
 impl <'a, 'tcx: 'a> Clone for EvalContext<'a, 'tcx> {
@@ -50,6 +67,10 @@ impl <'a, 'tcx: 'a> Clone for EvalContext<'a, 'tcx> {
             stack: self.stack.clone(),
             stack_limit: self.stack_limit,
             steps_remaining: self.steps_remaining,
+            symbolic_allocation_size: self.symbolic_allocation_size,
+            branch_path: self.branch_path.clone(),
+            side_errors: self.side_errors.clone(),
+            side_paths: self.side_paths.clone(),
         }
     }
 }
@@ -133,6 +154,7 @@ pub struct ResourceLimits {
     pub memory_size: u64,
     pub step_limit: u64,
     pub stack_limit: usize,
+    pub symbolic_allocation_size: u64,
 }
 
 impl Default for ResourceLimits {
@@ -141,6 +163,7 @@ impl Default for ResourceLimits {
             memory_size: 100 * 1024 * 1024, // 100 MB
             step_limit: 1_000_000,
             stack_limit: 100,
+            symbolic_allocation_size: 4096,
         }
     }
 }
@@ -154,6 +177,10 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             stack: Vec::new(),
             stack_limit: limits.stack_limit,
             steps_remaining: limits.step_limit,
+            symbolic_allocation_size: limits.symbolic_allocation_size,
+            branch_path: Vec::new(),
+            side_errors: Vec::new(),
+            side_paths: Vec::new(),
         }
     }
 
@@ -209,12 +236,11 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 PrimVal::Ptr(ptr)
             }
 
-            Struct(_)    => unimplemented!(),
-            Tuple(_)     => unimplemented!(),
             Function(_, _)  => PrimVal::Undef,
-            Array(_)     => unimplemented!(),
-            Repeat(_, _) => unimplemented!(),
-            Variant(..) => unimplemented!(),
+
+            Struct(_) | Tuple(_) | Array(_) | Repeat(..) | Variant(..) =>
+                return Err(EvalError::Unimplemented(
+                    format!("aggregate constant {:?}", const_val))),
         };
 
         Ok(Value::ByVal(primval))
@@ -384,7 +410,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         // FIXME(solson)
         let dest_ptr = self.force_allocation(dest)?.to_ptr();
 
-        let discr_dest = dest_ptr.offset(discr_offset);
+        let discr_dest = dest_ptr.offset(discr_offset)?;
         self.memory.write_uint(discr_dest, discr_val, discr_size)?;
 
         let dest = Lvalue::Ptr {
@@ -470,7 +496,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     Univariant { ref variant, .. } => {
                         if variant.packed {
                             let ptr = self.force_allocation(dest)?.to_ptr_and_extra().0;
-                            self.memory.mark_packed(ptr, variant.stride().bytes());
+                            self.memory.mark_packed(ptr, variant.stride().bytes())?;
                         }
                         self.assign_fields(dest, dest_ty, operands)?;
                     }
@@ -488,7 +514,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             let discr_size = discr.size().bytes();
                             if variants[variant].packed {
                                 let ptr = self.force_allocation(dest)?.to_ptr_and_extra().0;
-                                self.memory.mark_packed(ptr, variants[variant].stride().bytes());
+                                self.memory.mark_packed(ptr, variants[variant].stride().bytes())?;
                             }
 
                             self.assign_discr_and_fields(
@@ -530,7 +556,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         if let mir::AggregateKind::Adt(_, variant, _, _) = **kind {
                             if nonnull.packed {
                                 let ptr = self.force_allocation(dest)?.to_ptr_and_extra().0;
-                                self.memory.mark_packed(ptr, nonnull.stride().bytes());
+                                self.memory.mark_packed(ptr, nonnull.stride().bytes())?;
                             }
                             if nndiscr == variant as u64 {
                                 self.assign_fields(dest, dest_ty, operands)?;
@@ -544,7 +570,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                                 // FIXME(solson)
                                 let dest = self.force_allocation(dest)?.to_ptr();
 
-                                let dest = dest.offset(offset.bytes());
+                                let dest = dest.offset(offset.bytes())?;
                                 let dest_size = self.type_size(ty)?
                                     .expect("bad StructWrappedNullablePointer discrfield");
                                 self.memory.write_int(dest, 0, dest_size)?;
@@ -604,7 +630,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let dest = self.force_allocation(dest)?.to_ptr();
 
                 for i in 0..length {
-                    let elem_dest = dest.offset(i * elem_size);
+                    let elem_dest = dest.offset(i * elem_size)?;
                     self.write_value_to_ptr(value, elem_dest, elem_ty)?;
                 }
             }
@@ -613,7 +639,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let src = self.eval_lvalue(lvalue)?;
                 let ty = self.lvalue_ty(lvalue);
                 let (_, len) = src.elem_ty_and_len(ty);
-                self.write_primval(dest, PrimVal::from_u128(len as u128), dest_ty)?;
+                self.write_primval(dest, len, dest_ty)?;
             }
 
             Ref(_, _, ref lvalue) => {
@@ -623,7 +649,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
                 let val = match extra {
                     LvalueExtra::None => Value::ByVal(ptr),
-                    LvalueExtra::Length(len) => Value::ByValPair(ptr, PrimVal::from_u128(len as u128)),
+                    LvalueExtra::Length(len) => Value::ByValPair(ptr, len),
                     LvalueExtra::Vtable(vtable) => Value::ByValPair(ptr, PrimVal::Ptr(vtable)),
                     LvalueExtra::DowncastVariant(..) =>
                         bug!("attempted to take a reference to an enum downcast lvalue"),
@@ -717,13 +743,18 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let ptr = self.force_allocation(lval)?.to_ptr();
                 let discr_val = self.read_discriminant_value(ptr, ty)?;
                 if let ty::TyAdt(adt_def, _) = ty.sty {
-                    if adt_def.discriminants(self.tcx).all(|v| discr_val != v.to_u128_unchecked()) {
-                        return Err(EvalError::InvalidDiscriminant);
+                    if discr_val.is_concrete() {
+                        let discr_val = discr_val.to_u128()?;
+                        if adt_def.discriminants(self.tcx).all(|v| discr_val != v.to_u128_unchecked()) {
+                            return Err(EvalError::InvalidDiscriminant);
+                        }
+                    } else {
+                        self.ensure_valid_abstract_discriminant(discr_val, ty, adt_def)?;
                     }
                 } else {
                     bug!("rustc only generates Rvalue::Discriminant for enums");
                 }
-                self.write_primval(dest, PrimVal::Bytes(discr_val), dest_ty)?;
+                self.write_primval(dest, discr_val, dest_ty)?;
             },
         }
 
@@ -852,7 +883,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         let pointee_size =
             self.type_size(pointee_ty)?.expect("cannot offset a pointer to an unsized type") as i64;
         // FIXME: Check overflow, out-of-bounds
-        Ok(ptr.signed_offset(offset * pointee_size))
+        ptr.signed_offset(offset * pointee_size)
     }
 
     pub(super) fn eval_operand_to_primval(&mut self, op: &mir::Operand<'tcx>) -> EvalResult<'tcx, PrimVal> {
@@ -899,6 +930,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     fn copy(&mut self, src: Pointer, dest: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx> {
         let size = self.type_size(ty)?.expect("cannot copy from an unsized type");
         let align = self.type_align(ty)?;
+        self.check_abstract_size(src, size)?;
+        self.check_abstract_size(dest, size)?;
         self.memory.copy(src, dest, size, align)?;
         Ok(())
     }
@@ -1080,9 +1113,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             Value::ByRef(ptr) => self.copy(ptr, dest, dest_ty),
             Value::ByVal(primval) => {
                 let size = self.type_size(dest_ty)?.expect("dest type must be sized");
+                self.check_abstract_size(dest, size)?;
                 self.memory.write_primval(dest, primval, size)
             }
-            Value::ByValPair(a, b) => self.write_pair_to_ptr(a, b, dest, dest_ty),
+            Value::ByValPair(a, b) => {
+                let size = self.type_size(dest_ty)?.expect("dest type must be sized");
+                self.check_abstract_size(dest, size)?;
+                self.write_pair_to_ptr(a, b, dest, dest_ty)
+            }
         }
     }
 
@@ -1103,8 +1141,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         let field_1_ty = self.get_field_ty(ty, 1)?;
         let field_0_size = self.type_size(field_0_ty)?.expect("pair element type must be sized");
         let field_1_size = self.type_size(field_1_ty)?.expect("pair element type must be sized");
-        self.memory.write_primval(ptr.offset(field_0), a, field_0_size)?;
-        self.memory.write_primval(ptr.offset(field_1), b, field_1_size)?;
+        self.memory.write_primval(ptr.offset(field_0)?, a, field_0_size)?;
+        self.memory.write_primval(ptr.offset(field_1)?, b, field_1_size)?;
         Ok(())
     }
 
@@ -1200,13 +1238,182 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         match ty.sty {
             ty::TyBool if val.is_concrete() && val.to_bytes()? > 1 => Err(EvalError::InvalidBool),
 
-            ty::TyChar if ::std::char::from_u32(val.to_bytes()? as u32).is_none()
+            ty::TyChar if val.is_concrete() && ::std::char::from_u32(val.to_bytes()? as u32).is_none()
                 => Err(EvalError::InvalidChar(val.to_bytes()? as u32 as u128)),
 
             _ => Ok(()),
         }
     }
 
+    /// Makes the current path assume the abstract bool `valid`. If `valid` can also
+    /// be false, the path where it is false splits off as a side error, described by
+    /// `error`. Fails with that error right away if `valid` cannot hold.
+    pub(crate) fn require_valid<F>(&mut self, valid: PrimVal, error: F) -> EvalResult<'tcx>
+        where F: FnOnce(&Self, &[Constraint]) -> EvalResult<'tcx, EvalError<'tcx>>
+    {
+        let holds = vec![
+            Constraint::new_compare(mir::BinOp::Eq, PrimValKind::Bool, valid, PrimVal::from_bool(true))];
+        let fails = vec![
+            Constraint::new_compare(mir::BinOp::Eq, PrimValKind::Bool, valid, PrimVal::from_bool(false))];
+
+        if self.memory.constraints.is_feasible_with(&fails[..])? {
+            let e = error(&*self, &fails[..])?;
+            if !self.memory.constraints.is_feasible_with(&holds[..])? {
+                return Err(e);
+            }
+            let mut constraints = self.memory.constraints.clone();
+            for constraint in fails {
+                constraints.push_constraint(constraint);
+            }
+            self.side_errors.push((constraints, e));
+        }
+
+        for constraint in holds {
+            self.memory.constraints.push_constraint(constraint);
+        }
+        Ok(())
+    }
+
+    /// The number of bytes to allocate for a request of `size` bytes. An abstract
+    /// `size` gets the largest value that it can take on this path. It is kept within
+    /// `symbolic_allocation_size` and the free memory, and the path where it is larger
+    /// splits off as a side error.
+    pub(crate) fn allocation_size(&mut self, size: PrimVal) -> EvalResult<'tcx, u64> {
+        if size.is_concrete() {
+            return size.to_u64();
+        }
+
+        let memory_size = self.memory.memory_size();
+        let memory_usage = self.memory.memory_usage();
+        let symbolic_allocation_size = self.symbolic_allocation_size;
+        let limit = ::std::cmp::min(symbolic_allocation_size, memory_size - memory_usage);
+
+        let within_limit = self.memory.constraints.add_binop_constraint(
+            mir::BinOp::Le, size, PrimVal::Bytes(limit as u128), PrimValKind::U64)?;
+        self.require_valid(within_limit, move |this, fails| {
+            let allocation_size = this.witness_u64(size, fails)?;
+            if allocation_size > memory_size - memory_usage {
+                Ok(EvalError::OutOfMemory { allocation_size, memory_size, memory_usage })
+            } else {
+                Ok(EvalError::Unimplemented(format!(
+                    "allocation of {} bytes, but allocations of abstract size are limited to {} bytes",
+                    allocation_size, symbolic_allocation_size)))
+            }
+        })?;
+
+        // Find the largest feasible size by bisection.
+        let (mut low, mut high) = (0, limit);
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            let at_least = [
+                Constraint::new_compare(mir::BinOp::Ge, PrimValKind::U64, size, PrimVal::Bytes(mid as u128))];
+            if self.memory.constraints.is_feasible_with(&at_least[..])? {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Requires the `size` bytes at `ptr` to lie within the size that the program
+    /// asked for, if that size is abstract. The path where they do not splits off
+    /// as a `PointerOutOfBounds` error.
+    pub(crate) fn check_abstract_size(&mut self, ptr: Pointer, size: u64) -> EvalResult<'tcx> {
+        if size == 0 || ptr.points_to_zst() {
+            return Ok(());
+        }
+        let allocation_size = match self.memory.get(ptr.alloc_id).ok().and_then(|alloc| alloc.abstract_size) {
+            Some(allocation_size) => allocation_size,
+            None => return Ok(()),
+        };
+
+        let usize = self.tcx.types.usize;
+        let end = self.binary_op(
+            mir::BinOp::Add, ptr.offset.to_primval(), usize, PrimVal::Bytes(size as u128), usize)?.0;
+        let in_bounds = self.binary_op(mir::BinOp::Le, end, usize, allocation_size, usize)?.0;
+        self.require_valid(in_bounds, move |this, fails| {
+            let ptr = Pointer::new(ptr.alloc_id, this.witness_u64(ptr.offset.to_primval(), fails)?);
+            let allocation_size = this.witness_u64(allocation_size, fails)?;
+            Ok(EvalError::PointerOutOfBounds { ptr, size, allocation_size })
+        })
+    }
+
+    /// Turns a byte of memory that is read as a bool into a bool. An abstract byte
+    /// that was not written as a bool has to be 0 or 1.
+    fn read_abstract_bool(&mut self, val: PrimVal) -> EvalResult<'tcx, PrimVal> {
+        match val {
+            PrimVal::Abstract(sbytes) if !self.memory.constraints.is_abstract_bool(sbytes[0]) => {
+                let valid = self.memory.constraints.add_binop_constraint(
+                    mir::BinOp::Le, val, PrimVal::Bytes(1), PrimValKind::U8)?;
+                self.require_valid(valid, |_, _| Ok(EvalError::InvalidBool))?;
+                self.memory.constraints.add_binop_constraint(
+                    mir::BinOp::Ne, val, PrimVal::Bytes(0), PrimValKind::U8)
+            }
+            _ => Ok(val),
+        }
+    }
+
+    /// Requires the abstract `val` of `kind` to be a valid char, that is, at most
+    /// 0x10ffff and not a surrogate.
+    pub(crate) fn ensure_valid_abstract_char(
+        &mut self,
+        val: PrimVal,
+        kind: PrimValKind,
+    ) -> EvalResult<'tcx> {
+        let valid = {
+            let constraints = &mut self.memory.constraints;
+            let in_range = constraints.add_binop_constraint(
+                mir::BinOp::Le, val, PrimVal::Bytes(0x10ffff), kind)?;
+            let below = constraints.add_binop_constraint(
+                mir::BinOp::Lt, val, PrimVal::Bytes(0xd800), kind)?;
+            let above = constraints.add_binop_constraint(
+                mir::BinOp::Gt, val, PrimVal::Bytes(0xdfff), kind)?;
+            let not_surrogate = constraints.add_binop_constraint(
+                mir::BinOp::BitOr, below, above, PrimValKind::Bool)?;
+            constraints.add_binop_constraint(
+                mir::BinOp::BitAnd, in_range, not_surrogate, PrimValKind::Bool)?
+        };
+        self.require_valid(valid, |ecx, fails| {
+            let c = ecx.memory.constraints.get_satisfying_value(fails, val, kind)?;
+            Ok(EvalError::InvalidChar(c as u128))
+        })
+    }
+
+    /// Requires the abstract discriminant `discr_val` of the enum `ty` to be one of
+    /// its discriminants.
+    fn ensure_valid_abstract_discriminant(
+        &mut self,
+        discr_val: PrimVal,
+        ty: Ty<'tcx>,
+        adt_def: &'tcx ty::AdtDef,
+    ) -> EvalResult<'tcx> {
+        let kind = match *self.type_layout(ty)? {
+            Layout::CEnum { discr, .. } | Layout::General { discr, .. } =>
+                PrimValKind::from_uint_size(discr.size().bytes()),
+
+            // Null or not, the discriminant is valid.
+            Layout::RawNullablePointer { .. } |
+            Layout::StructWrappedNullablePointer { .. } => return Ok(()),
+
+            _ => {
+                let msg = format!("abstract discriminant of {:?}", ty);
+                return Err(EvalError::Unimplemented(msg));
+            }
+        };
+        let mask = u128::max_value() >> (128 - 8 * kind.num_bytes());
+
+        let mut valid = PrimVal::from_bool(false);
+        for discr in adt_def.discriminants(self.tcx) {
+            let discr = PrimVal::Bytes(discr.to_u128_unchecked() & mask);
+            let is_discr = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::Eq, discr_val, discr, kind)?;
+            valid = self.memory.constraints.add_binop_constraint(
+                mir::BinOp::BitOr, valid, is_discr, PrimValKind::Bool)?;
+        }
+        self.require_valid(valid, |_, _| Ok(EvalError::InvalidDiscriminant))
+    }
+
     pub(super) fn read_value(&mut self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx, Value> {
         if let Some(val) = self.try_read_value(ptr, ty)? {
             Ok(val)
@@ -1221,7 +1428,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             Ok(Value::ByVal(PrimVal::Ptr(p)))
         } else {
             trace!("reading fat pointer extra of type {}", pointee_ty);
-            let extra = ptr.offset(self.memory.pointer_size());
+            let extra = ptr.offset(self.memory.pointer_size())?;
             let extra = match self.tcx.struct_tail(pointee_ty).sty {
                 ty::TyDynamic(..) => PrimVal::Ptr(self.memory.read_ptr(extra)?),
                 ty::TySlice(..) |
@@ -1235,12 +1442,18 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     fn try_read_value(&mut self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx, Option<Value>> {
         use syntax::ast::FloatTy;
 
+        if let Some(size) = self.type_size(ty)? {
+            self.check_abstract_size(ptr, size)?;
+        }
         if !ptr.is_concrete() {
             return Ok(None);
         }
 
         let val = match ty.sty {
-            ty::TyBool => self.memory.read_bool(ptr)?,
+            ty::TyBool => {
+                let b = self.memory.read_bool(ptr)?;
+                self.read_abstract_bool(b)?
+            }
             ty::TyChar => {
                 let c = self.memory.read_uint(ptr, 4)?;
                 match c {
@@ -1250,7 +1463,10 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             None => return Err(EvalError::InvalidChar(b as u128)),
                         }
                     }
-                    _ => unimplemented!(),
+                    _ => {
+                        self.ensure_valid_abstract_char(c, PrimValKind::U32)?;
+                        c
+                    }
                 }
             }
 
@@ -1314,6 +1530,17 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         self.stack.last().expect("no call frames exist")
     }
 
+    /// The span of the statement or terminator that is executed next.
+    pub(crate) fn current_span(&self) -> Span {
+        let frame = self.frame();
+        let block = &frame.mir.basic_blocks()[frame.block];
+        if frame.stmt < block.statements.len() {
+            block.statements[frame.stmt].source_info.span
+        } else {
+            block.terminator().source_info.span
+        }
+    }
+
     pub(super) fn frame_mut(&mut self) -> &mut Frame<'tcx> {
         self.stack.last_mut().expect("no call frames exist")
     }
@@ -1415,8 +1642,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     }
                     let src_field_offset = self.get_field_offset(src_ty, i)?.bytes();
                     let dst_field_offset = self.get_field_offset(dest_ty, i)?.bytes();
-                    let src_f_ptr = src_ptr.offset(src_field_offset);
-                    let dst_f_ptr = dest.offset(dst_field_offset);
+                    let src_f_ptr = src_ptr.offset(src_field_offset)?;
+                    let dst_f_ptr = dest.offset(dst_field_offset)?;
                     if src_fty == dst_fty {
                         self.copy(src_f_ptr, dst_f_ptr, src_fty)?;
                     } else {
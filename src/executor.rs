@@ -1,6 +1,6 @@
-use std::collections::VecDeque;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::time::{Duration, Instant};
 
 use rustc::hir::def_id::DefId;
 use rustc::hir::map::definitions::DefPathData;
@@ -13,12 +13,23 @@ use error::{StaticEvalError, EvalError};
 use lvalue::{Lvalue};
 use memory::{Pointer};
 use eval_context::{EvalContext, Frame, ResourceLimits, StackPopCleanup};
+use search::{Coverage, SearchStrategy, SearchStrategyBuilder, SearchStrategyKind};
 use value::{PrimVal};
 
 pub struct Executor<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
-    queue: VecDeque<EvalContext<'a, 'tcx>>,
+    queue: Box<SearchStrategy<'a, 'tcx> + 'a>,
+    coverage: Coverage,
     config: ExecutionConfig,
+
+    /// When exploration started, for `ExecutionConfig::max_time`.
+    start: Instant,
+
+    /// The number of steps taken so far, over all paths.
+    steps: u64,
+
+    /// The number of paths that have been handed to the consumer so far.
+    paths: u64,
 }
 
 pub struct FinishStep<'tcx> {
@@ -38,6 +49,15 @@ pub enum FinishStepVariant<'tcx> {
 pub struct ExecutionConfig {
     consumer: Option<Rc<RefCell<FnMut(ExecutionComplete) -> bool>>>,
     emit_error: bool,
+    search_strategy: SearchStrategyKind,
+    custom_search_strategy: Option<Rc<SearchStrategyBuilder>>,
+    workers: usize,
+    worker: Option<Worker>,
+    max_time: Option<Duration>,
+    max_paths: Option<u64>,
+    max_steps: Option<u64>,
+    max_queue_len: Option<usize>,
+    solver_timeout: Option<Duration>,
 }
 
 impl ExecutionConfig {
@@ -45,6 +65,15 @@ impl ExecutionConfig {
         ExecutionConfig {
             consumer: None,
             emit_error: false,
+            search_strategy: SearchStrategyKind::default(),
+            custom_search_strategy: None,
+            workers: 1,
+            worker: None,
+            max_time: None,
+            max_paths: None,
+            max_steps: None,
+            max_queue_len: None,
+            solver_timeout: None,
         }
     }
 
@@ -53,6 +82,75 @@ impl ExecutionConfig {
         self
     }
 
+    /// Selects the order in which pending paths are explored. Defaults to
+    /// `SearchStrategyKind::DepthFirst`.
+    pub fn search_strategy<'a>(&'a mut self, strategy: SearchStrategyKind) -> &'a mut Self {
+        self.search_strategy = strategy;
+        self.custom_search_strategy = None;
+        self
+    }
+
+    /// Explores pending paths in the order decided by a user-defined strategy.
+    /// `build` is called with the type context of the program once per
+    /// exploration, and once in each worker process. Overrides `search_strategy`.
+    pub fn custom_search_strategy<'a, F>(&'a mut self, build: F) -> &'a mut Self
+        where F: for<'b, 'tcx> Fn(TyCtxt<'b, 'tcx, 'tcx>) -> Box<SearchStrategy<'b, 'tcx> + 'b> + 'static
+    {
+        self.custom_search_strategy = Some(Rc::new(build));
+        self
+    }
+
+    /// Splits exploration across `n` forked worker processes, each of which
+    /// compiles the program in its own rustc session. Results from all workers
+    /// are handed to the consumer in the calling process, in no particular order.
+    /// Defaults to 1, which explores everything in the calling process.
+    ///
+    /// Forking is only safe from a single-threaded process, so with more than one
+    /// worker, `run` panics if the calling process has other threads. In
+    /// particular, it cannot be called from a test run by the libtest harness.
+    pub fn workers<'a>(&'a mut self, n: usize) -> &'a mut Self {
+        assert!(n > 0, "need at least one worker");
+        self.workers = n;
+        self
+    }
+
+    /// Stops exploration once it has run for `time`. Compilation of the program
+    /// does not count.
+    pub fn max_time<'a>(&'a mut self, time: Duration) -> &'a mut Self {
+        self.max_time = Some(time);
+        self
+    }
+
+    /// Stops exploration once `n` paths have been handed to the consumer.
+    pub fn max_paths<'a>(&'a mut self, n: u64) -> &'a mut Self {
+        self.max_paths = Some(n);
+        self
+    }
+
+    /// Stops exploration once `n` MIR statements and terminators have been
+    /// executed, summed over all paths. Unlike the `step_limit` attribute, which
+    /// each path gets anew when it forks, this bounds the total work. With worker
+    /// processes, each worker gets an equal share, rounded down.
+    pub fn max_steps<'a>(&'a mut self, n: u64) -> &'a mut Self {
+        self.max_steps = Some(n);
+        self
+    }
+
+    /// Stops exploration once more than `n` paths are waiting to be explored. With
+    /// worker processes, each worker gets an equal share, rounded down.
+    pub fn max_queue_len<'a>(&'a mut self, n: usize) -> &'a mut Self {
+        self.max_queue_len = Some(n);
+        self
+    }
+
+    /// Gives up on a solver query after `timeout`. The path that made the query
+    /// then ends with `EvalError::SolverUnknown`. By default, queries can take
+    /// arbitrarily long.
+    pub fn solver_timeout<'a>(&'a mut self, timeout: Duration) -> &'a mut Self {
+        self.solver_timeout = Some(timeout);
+        self
+    }
+
     /// The consumer returns `true` if it wants the executor to continue.
     pub fn consumer<'a, F>(
         &'a mut self, consumer: F)
@@ -63,11 +161,125 @@ impl ExecutionConfig {
         self
     }
 
-    pub fn run(&self, args: Vec<String>) {
-        ::driver::main_helper(args, self.clone());
+    /// Compiles and explores the program. With worker processes, `max_paths`
+    /// counts the paths of all workers together, and `max_time` bounds each of the
+    /// workers, which run at the same time.
+    pub fn run(&self, args: Vec<String>) -> ExecutionStatus {
+        if self.workers > 1 {
+            self.run_workers(args)
+        } else {
+            self.run_in_process(args)
+        }
+    }
+
+    fn run_in_process(&self, args: Vec<String>) -> ExecutionStatus {
+        ::driver::main_helper(args, self.clone())
+    }
+
+    #[cfg(unix)]
+    fn run_workers(&self, args: Vec<String>) -> ExecutionStatus {
+        ::parallel::run_workers(self, self.workers, args)
+    }
+
+    #[cfg(not(unix))]
+    fn run_workers(&self, args: Vec<String>) -> ExecutionStatus {
+        warn!("worker processes are only supported on unix; exploring in this process");
+        self.run_in_process(args)
+    }
+
+    pub(crate) fn max_paths_budget(&self) -> Option<u64> {
+        self.max_paths
+    }
+
+    /// Hands `complete` to the consumer. Returns `false` if the consumer wants
+    /// exploration to stop.
+    pub(crate) fn consume(&self, complete: ExecutionComplete) -> bool {
+        match self.consumer {
+            Some(ref f) => (&mut *f.borrow_mut())(complete),
+            None => true,
+        }
+    }
+
+    pub(crate) fn has_consumer(&self) -> bool {
+        self.consumer.is_some()
+    }
+
+    /// The configuration that runs inside the worker process `worker`. The step
+    /// and queue budgets are split between the workers, so that together they
+    /// stay within them.
+    pub(crate) fn for_worker(&self, worker: Worker) -> Self {
+        let mut config = self.clone();
+        config.workers = 1;
+        config.worker = Some(worker);
+        config.max_steps = self.max_steps.map(|n| n / worker.count as u64);
+        config.max_queue_len = self.max_queue_len.map(|n| n / worker.count);
+        config
+    }
+}
+
+/// One of several worker processes that share the exploration of a program.
+///
+/// Every worker explores the same paths until they have passed `split_depth`
+/// branch points. From there on, each path belongs to exactly one worker, which
+/// is chosen by hashing the branch decisions that lead to it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Worker {
+    pub index: usize,
+    pub count: usize,
+    pub split_depth: usize,
+}
+
+impl Worker {
+    /// Whether this worker is responsible for paths starting with `branch_path`.
+    fn owns(&self, branch_path: &[u32]) -> bool {
+        // FNV-1a, so that all workers agree on the owner.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for &b in &branch_path[..::std::cmp::min(branch_path.len(), self.split_depth)] {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        hash % self.count as u64 == self.index as u64
+    }
+
+    /// Whether this worker should keep exploring a path that has just forked.
+    fn keeps(&self, branch_path: &[u32]) -> bool {
+        branch_path.len() != self.split_depth || self.owns(branch_path)
+    }
+
+    /// Whether this worker should report a path that has finished. Paths that
+    /// end before the split are explored by every worker, so only their owner
+    /// reports them.
+    fn reports(&self, branch_path: &[u32]) -> bool {
+        branch_path.len() > self.split_depth || self.owns(branch_path)
     }
 }
 
+/// How a run of the executor ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+pub enum ExecutionStatus {
+    /// Every feasible path was explored.
+    Complete,
+
+    /// The consumer asked to stop.
+    Stopped,
+
+    /// Exploration stopped early because a budget ran out.
+    Truncated(Budget),
+
+    /// A worker process died before it finished, so its share of the paths
+    /// may not have been explored.
+    WorkerFailed,
+}
+
+/// The executor-wide budgets of `ExecutionConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+pub enum Budget {
+    Time,
+    Paths,
+    Steps,
+    QueueLength,
+}
+
 #[derive(Debug)]
 pub struct ExecutionComplete {
     pub input: Vec<u8>,
@@ -84,20 +296,32 @@ impl <'a, 'tcx: 'a> Executor<'a, 'tcx> {
         -> Self
     {
 
+        let queue = match config.custom_search_strategy {
+            Some(ref build) => build(tcx),
+            None => config.search_strategy.build(),
+        };
+
         let mut result = Executor {
             tcx: tcx,
-            queue: VecDeque::new(),
+            queue: queue,
+            coverage: Coverage::new(),
             config: config,
+            start: Instant::now(),
+            steps: 0,
+            paths: 0,
         };
 
         let mut ecx = EvalContext::new(tcx, limits);
+        if let Some(timeout) = result.config.solver_timeout {
+            ecx.memory.constraints.set_solver_timeout(timeout);
+        }
         let instance = ty::Instance::mono(tcx, def_id);
         let mir = ecx.load_mir(instance.def).expect("main function's MIR not found");
 
         if !mir.return_ty.is_nil() || mir.arg_count > 0 {
             let msg = "seer does not support main functions without `fn()` type signatures";
             tcx.sess.err(&EvalError::Unimplemented(String::from(msg)).to_string());
-            unimplemented!()
+            return result;
         }
 
         ecx.push_stack_frame(
@@ -114,100 +338,214 @@ impl <'a, 'tcx: 'a> Executor<'a, 'tcx> {
     }
 
     pub fn push_eval_context(&mut self, ecx: EvalContext<'a, 'tcx>) {
-        // Push onto the front so that we go depth-first.
-        // Pushing onto the back consumes more memory and may be less
-        // cache-friendly.
-        self.queue.push_front(ecx);
+        self.queue.push(ecx);
     }
 
-    fn pop_eval_context(&mut self) -> Option<EvalContext<'a, 'tcx>> {
-        self.queue.pop_front()
+    pub fn run(&mut self) -> ExecutionStatus {
+        let status = self.explore();
+        info!("covered {} basic blocks", self.coverage.len());
+        if let ExecutionStatus::Truncated(budget) = status {
+            warn!("exploration truncated: {:?} budget exhausted after {} paths and {} steps",
+                  budget, self.paths, self.steps);
+        }
+        status
     }
 
-    pub fn run(&mut self) {
-        while let Some(mut ecx) = self.pop_eval_context() {
-            match ecx.step() {
-                Ok((true, None)) => {
-                    self.push_eval_context(ecx)
+    fn explore(&mut self) -> ExecutionStatus {
+        while let Some(mut ecx) = self.queue.pop(&self.coverage) {
+            // Keep going along the current path until it branches or ends.
+            loop {
+                if let Some(budget) = self.exhausted_budget() {
+                    return ExecutionStatus::Truncated(budget);
                 }
-                Ok((true, Some(branches))) => {
-                    if branches.is_empty() {
-                        // no feasible branch. should throw error
-                        unimplemented!()
-                    } else {
-                        let iter = ::std::iter::repeat(ecx).zip(branches.into_iter());
-                        for (mut cx, finish_step) in iter {
-                            let FinishStep {constraints, variant} = finish_step;
-                            for constraint in constraints {
-                                cx.memory.constraints.push_constraint(constraint);
-                                match variant {
-                                    FinishStepVariant::Continue { goto_block, set_lvalue} => {
-                                        if let Some((lvalue, prim, ty)) = set_lvalue {
-                                            if let Err(_) = cx.write_primval(lvalue, prim, ty) {
-                                                unimplemented!()
-                                            }
-                                        }
-                                        cx.goto_block(goto_block);
-                                    }
-                                    FinishStepVariant::Error(_) => {
-                                        unimplemented!()
-                                    }
-                                }
-                            }
-                            self.push_eval_context(cx);
+                self.coverage.record(&ecx);
+                self.steps += 1;
+                let result = ecx.step();
+                if !self.report_side_errors(&mut ecx) {
+                    return ExecutionStatus::Stopped;
+                }
+                if !self.queue_side_paths(&mut ecx) {
+                    // Another worker explores this path.
+                    break;
+                }
+                match result {
+                    Ok((true, None)) => {}
+                    Ok((true, Some(branches))) => {
+                        if !self.branch(ecx, branches) {
+                            return ExecutionStatus::Stopped;
                         }
+                        break;
                     }
-                }
-                Ok((false, _)) => {
-                    let go_on = match self.config.consumer {
-                        Some(ref f) => {
-                            (&mut *f.borrow_mut())(ExecutionComplete {
-                                input: ecx.memory.constraints.get_satisfying_values(),
-                                result: Ok(())
-                            })
+                    Ok((false, _)) => {
+                        let go_on = self.report_complete(&ecx, Ok(()));
+                        let leaks = ecx.memory.leak_report();
+                        if leaks != 0 {
+                            self.tcx.sess.err("the evaluated program leaked memory");
+                        }
+                        if !go_on {
+                            return ExecutionStatus::Stopped;
                         }
-                        None => true,
-                    };
-                    let leaks = ecx.memory.leak_report();
-                    if leaks != 0 {
-                        self.tcx.sess.err("the evaluated program leaked memory");
+                        break;
                     }
-                    if !go_on {
-                        break
+                    Err(e) => {
+                        if !self.report_error(&ecx, e) {
+                            return ExecutionStatus::Stopped;
+                        }
+                        break;
                     }
                 }
-                Err(e) => {
-                    if self.config.emit_error {
-                        report(self.tcx, &ecx, e.clone());
-                    }
+            }
+        }
+        ExecutionStatus::Complete
+    }
 
-                    match self.config.consumer {
-                        Some(ref f) => {
-                            let go_on = (&mut *f.borrow_mut())(ExecutionComplete {
-                                input: ecx.memory.constraints.get_satisfying_values(),
-                                result: Err(e.into())
-                            });
-                            if !go_on {
-                                break
+    /// Returns the first executor-wide budget that has run out, if any.
+    fn exhausted_budget(&self) -> Option<Budget> {
+        let config = &self.config;
+        if config.max_paths.map_or(false, |n| self.paths >= n) {
+            Some(Budget::Paths)
+        } else if config.max_steps.map_or(false, |n| self.steps >= n) {
+            Some(Budget::Steps)
+        } else if config.max_queue_len.map_or(false, |n| self.queue.len() > n) {
+            Some(Budget::QueueLength)
+        } else if config.max_time.map_or(false, |t| self.start.elapsed() >= t) {
+            Some(Budget::Time)
+        } else {
+            None
+        }
+    }
+
+    /// Splits `ecx` into one path per branch. Returns `false` if the consumer
+    /// wants exploration to stop.
+    fn branch(&mut self, ecx: EvalContext<'a, 'tcx>, branches: Vec<FinishStep<'tcx>>) -> bool {
+        if branches.is_empty() {
+            // None of the successors is feasible, which means that the current
+            // path could not actually have been taken. Drop it.
+            warn!("no feasible branch; dropping path");
+        }
+        let forked = branches.len() > 1;
+        for (idx, FinishStep { constraints, variant }) in branches.into_iter().enumerate() {
+            let mut cx = ecx.clone();
+            if forked {
+                cx.branch_path.push(idx as u32);
+                if let Some(worker) = self.config.worker {
+                    if !worker.keeps(&cx.branch_path) {
+                        // Another worker explores this one.
+                        continue;
+                    }
+                }
+            }
+            for constraint in constraints {
+                cx.memory.constraints.push_constraint(constraint);
+            }
+            match variant {
+                FinishStepVariant::Continue { goto_block, set_lvalue } => {
+                    if let Some((lvalue, prim, ty)) = set_lvalue {
+                        if let Err(e) = cx.write_primval(lvalue, prim, ty) {
+                            if !self.report_error(&cx, e) {
+                                return false;
                             }
+                            continue;
                         }
-                        None => (),
+                    }
+                    cx.goto_block(goto_block);
+                    self.push_eval_context(cx);
+                }
+                FinishStepVariant::Error(e) => {
+                    if !self.report_error(&cx, e) {
+                        return false;
                     }
                 }
             }
         }
+        true
+    }
+
+    /// Reports the paths that split off `ecx` during its last step because an
+    /// abstract value could violate a validity invariant. Returns `false` if the
+    /// consumer wants exploration to stop.
+    fn report_side_errors(&mut self, ecx: &mut EvalContext<'a, 'tcx>) -> bool {
+        for (constraints, e) in ::std::mem::replace(&mut ecx.side_errors, Vec::new()) {
+            let mut cx = ecx.clone();
+            cx.memory.constraints = constraints;
+            if !self.report_error(&cx, e) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Queues the paths that split off `ecx` during its last step, as if `ecx` had
+    /// branched with itself as the first branch. Returns `false` if `ecx` belongs to
+    /// another worker.
+    fn queue_side_paths(&mut self, ecx: &mut EvalContext<'a, 'tcx>) -> bool {
+        let side_paths = ::std::mem::replace(&mut ecx.side_paths, Vec::new());
+        if side_paths.is_empty() {
+            return true;
+        }
+        let branch_path = ecx.branch_path.clone();
+        for (idx, mut cx) in side_paths.into_iter().enumerate() {
+            cx.branch_path = branch_path.clone();
+            cx.branch_path.push(idx as u32 + 1);
+            if self.config.worker.map_or(true, |worker| worker.keeps(&cx.branch_path)) {
+                self.push_eval_context(cx);
+            }
+        }
+        ecx.branch_path.push(0);
+        self.config.worker.map_or(true, |worker| worker.keeps(&ecx.branch_path))
     }
-}
 
+    /// Reports a path that ended in `e`. Returns `false` if the consumer wants
+    /// exploration to stop.
+    ///
+    /// An `EvalError::Unimplemented` only means that seer could not follow the
+    /// path any further; it gets reported like any other error, and the rest of
+    /// the queue is explored as usual.
+    fn report_error(&mut self, ecx: &EvalContext<'a, 'tcx>, e: EvalError<'tcx>) -> bool {
+        if !self.reports(ecx) {
+            return true;
+        }
+        if self.config.emit_error {
+            report(self.tcx, ecx, e.clone());
+        }
+        self.report_complete(ecx, Err(e.into()))
+    }
+
+    /// Whether this process is the one that reports the finished path `ecx`, so
+    /// that a path that several workers explored is reported only once.
+    fn reports(&self, ecx: &EvalContext<'a, 'tcx>) -> bool {
+        self.config.worker.map_or(true, |worker| worker.reports(&ecx.branch_path))
+    }
+
+    /// Hands a finished path to the consumer, along with an input that drives
+    /// execution down that path. Returns `false` if the consumer wants exploration
+    /// to stop.
+    fn report_complete(
+        &mut self,
+        ecx: &EvalContext<'a, 'tcx>,
+        result: Result<(), StaticEvalError>)
+        -> bool
+    {
+        if !self.reports(ecx) {
+            return true;
+        }
+        self.paths += 1;
+        if !self.config.has_consumer() {
+            return true;
+        }
+        let complete = match ecx.memory.constraints.get_satisfying_values() {
+            Ok(input) => ExecutionComplete { input, result },
+
+            // We know how the path ended but cannot say which input leads there,
+            // so report it as incomplete.
+            Err(e) => ExecutionComplete { input: Vec::new(), result: Err(e.into()) },
+        };
+        self.config.consume(complete)
+    }
+}
 
 fn report(tcx: TyCtxt, ecx: &EvalContext, e: EvalError) {
-    let frame = ecx.stack().last().expect("stackframe was empty");
-    let block = &frame.mir.basic_blocks()[frame.block];
-    let span = if frame.stmt < block.statements.len() {
-        block.statements[frame.stmt].source_info.span
-    } else {
-        block.terminator().source_info.span
-    };
+    let span = ecx.current_span();
     let mut err = tcx.sess.struct_span_err(span, &e.to_string());
     for &Frame { instance, span, .. } in ecx.stack().iter().rev() {
         if tcx.def_key(instance.def_id()).disambiguated_data.data == DefPathData::ClosureExpr {
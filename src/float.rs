@@ -0,0 +1,432 @@
+//! IEEE 754 arithmetic on the bits of abstract floats.
+//!
+//! seer-z3 does not expose z3's floating-point theory, so abstract floats are
+//! bitvectors of their IEEE 754 encoding and every operation is spelled out in
+//! bitvector arithmetic, rounding to nearest with ties to even. All values are
+//! computed in `WIDTH` bits, which holds every intermediate result exactly; a
+//! result float or integer sits in the low bits with the high bits zero.
+
+use rustc::mir;
+use z3;
+
+use error::{EvalError, EvalResult};
+use value::PrimValKind;
+
+/// The width of every bitvector built here.
+const WIDTH: u32 = 128;
+
+/// An IEEE 754 binary interchange format.
+#[derive(Clone, Copy, Debug)]
+pub struct Format {
+    exponent_bits: u32,
+
+    /// Includes the implicit leading bit.
+    significand_bits: u32,
+}
+
+impl Format {
+    pub fn of(kind: PrimValKind) -> Option<Format> {
+        match kind {
+            PrimValKind::F32 => Some(Format { exponent_bits: 8, significand_bits: 24 }),
+            PrimValKind::F64 => Some(Format { exponent_bits: 11, significand_bits: 53 }),
+            _ => None,
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        self.exponent_bits + self.significand_bits
+    }
+
+    fn bias(self) -> i64 {
+        (1 << (self.exponent_bits - 1)) - 1
+    }
+
+    /// The number of significand bits that are stored explicitly.
+    fn fraction_bits(self) -> u32 {
+        self.significand_bits - 1
+    }
+
+    fn infinity_bits(self) -> u128 {
+        ((1 << self.exponent_bits) - 1) << self.fraction_bits()
+    }
+
+    fn sign_bit(self) -> u128 {
+        1 << (self.bits() - 1)
+    }
+}
+
+fn constant<'a>(ctx: &'a z3::Context, n: u128) -> z3::Ast<'a> {
+    z3::Ast::bv_from_u64(ctx, (n >> 64) as u64, 64)
+        .concat(&z3::Ast::bv_from_u64(ctx, n as u64, 64))
+}
+
+fn signed_constant<'a>(ctx: &'a z3::Context, n: i64) -> z3::Ast<'a> {
+    constant(ctx, n as i128 as u128)
+}
+
+/// Zero-extends `val`, which has `bits` bits, to `WIDTH` bits.
+pub fn widen<'a>(ctx: &'a z3::Context, val: z3::Ast<'a>, bits: u32) -> z3::Ast<'a> {
+    if bits == WIDTH {
+        val
+    } else {
+        z3::Ast::bv_from_u64(ctx, 0, WIDTH - bits).concat(&val)
+    }
+}
+
+fn bit_is_set<'a>(ctx: &'a z3::Context, val: &z3::Ast<'a>, bit: &z3::Ast<'a>) -> z3::Ast<'a> {
+    let one = constant(ctx, 1);
+    val.bvlshr(bit).bvand(&one)._eq(&one)
+}
+
+/// The low `bits` bits of `val`.
+fn low_bits<'a>(ctx: &'a z3::Context, val: &z3::Ast<'a>, bits: &z3::Ast<'a>) -> z3::Ast<'a> {
+    let one = constant(ctx, 1);
+    val.bvand(&one.bvshl(bits).bvsub(&one))
+}
+
+/// A float taken apart. Unless it is NaN or infinite, its value is
+/// `(-1)^sign * significand * 2^exponent`.
+struct Unpacked<'a> {
+    sign: z3::Ast<'a>,
+    nan: z3::Ast<'a>,
+    infinite: z3::Ast<'a>,
+    zero: z3::Ast<'a>,
+
+    /// Signed.
+    exponent: z3::Ast<'a>,
+    significand: z3::Ast<'a>,
+}
+
+fn unpack<'a>(ctx: &'a z3::Context, format: Format, val: &z3::Ast<'a>) -> Unpacked<'a> {
+    let fraction_bits = format.fraction_bits();
+    let zero = constant(ctx, 0);
+    let max_exponent = constant(ctx, (1 << format.exponent_bits) - 1);
+
+    let fraction = val.bvand(&constant(ctx, (1 << fraction_bits) - 1));
+    let biased = val.bvlshr(&constant(ctx, fraction_bits as u128)).bvand(&max_exponent);
+    let fraction_zero = fraction._eq(&zero);
+    let subnormal = biased._eq(&zero);
+    let special = biased._eq(&max_exponent);
+
+    Unpacked {
+        sign: bit_is_set(ctx, val, &constant(ctx, (format.bits() - 1) as u128)),
+        nan: special.and(&[&fraction_zero.not()]),
+        infinite: special.and(&[&fraction_zero]),
+        zero: subnormal.and(&[&fraction_zero]),
+        exponent: subnormal.ite(
+            &signed_constant(ctx, 1 - format.bias() - fraction_bits as i64),
+            &biased.bvadd(&signed_constant(ctx, -format.bias() - fraction_bits as i64))),
+        significand: subnormal.ite(&fraction, &fraction.bvor(&constant(ctx, 1 << fraction_bits))),
+    }
+}
+
+fn nan<'a>(ctx: &'a z3::Context, format: Format) -> z3::Ast<'a> {
+    // The quiet NaN that Rust's `NAN` constants have.
+    constant(ctx, format.infinity_bits() | 1 << (format.fraction_bits() - 1))
+}
+
+fn with_sign<'a>(ctx: &'a z3::Context, format: Format, sign: &z3::Ast<'a>, bits: u128) -> z3::Ast<'a> {
+    sign.ite(&constant(ctx, bits | format.sign_bit()), &constant(ctx, bits))
+}
+
+/// Shifts `significand` left until its highest set bit is the highest bit.
+/// Returns the shifted significand and the shift amount.
+fn normalize<'a>(ctx: &'a z3::Context, significand: z3::Ast<'a>) -> (z3::Ast<'a>, z3::Ast<'a>) {
+    let mut significand = significand;
+    let mut shift = constant(ctx, 0);
+    for &step in &[64, 32, 16, 8, 4, 2, 1] {
+        let high_clear = significand.bvult(&constant(ctx, 1 << (WIDTH - step)));
+        significand = high_clear.ite(&significand.bvshl(&constant(ctx, step as u128)), &significand);
+        shift = high_clear.ite(&shift.bvadd(&constant(ctx, step as u128)), &shift);
+    }
+    (significand, shift)
+}
+
+/// Encodes the float of `format` that is nearest to
+/// `(-1)^sign * significand * 2^exponent`, with ties to even.
+///
+/// Callers that cannot compute `significand` exactly set its lowest bit if
+/// they dropped anything. That is enough for correct rounding as long as the
+/// lowest bit lies at least two bits below the last bit that is kept.
+fn round<'a>(
+    ctx: &'a z3::Context,
+    format: Format,
+    sign: &z3::Ast<'a>,
+    exponent: z3::Ast<'a>,
+    significand: z3::Ast<'a>)
+    -> z3::Ast<'a>
+{
+    let zero = constant(ctx, 0);
+    let one = constant(ctx, 1);
+    let is_zero = significand._eq(&zero);
+
+    // After normalizing, the value lies in [2^top, 2^(top + 1)).
+    let (normalized, shift) = normalize(ctx, significand);
+    let top = exponent.bvsub(&shift).bvadd(&constant(ctx, (WIDTH - 1) as u128));
+    let biased = top.bvadd(&signed_constant(ctx, format.bias()));
+
+    // Keep `significand_bits` bits, or fewer for a subnormal result.
+    let subnormal = biased.bvslt(&one);
+    let extra = subnormal.ite(&one.bvsub(&biased), &zero);
+    let extra = extra.bvugt(&constant(ctx, WIDTH as u128)).ite(&constant(ctx, WIDTH as u128), &extra);
+    let drop = constant(ctx, (WIDTH - format.significand_bits) as u128).bvadd(&extra);
+
+    let kept = normalized.bvlshr(&drop);
+    let guard_bit = drop.bvsub(&one);
+    let guard = bit_is_set(ctx, &normalized, &guard_bit);
+    let sticky = low_bits(ctx, &normalized, &guard_bit)._eq(&zero).not();
+    let odd = kept.bvand(&one)._eq(&one);
+    let round_up = guard.and(&[&sticky.or(&[&odd])]);
+    let kept = kept.bvadd(&round_up.ite(&one, &zero));
+
+    // The implicit bit of `kept` carries into the exponent field, and so does a
+    // significand that rounding pushed to the next power of two. A subnormal
+    // result has an exponent field of zero, and rounds up into the smallest
+    // normal number the same way.
+    let field = subnormal.ite(&zero, &biased.bvsub(&one));
+    let bits = field.bvshl(&constant(ctx, format.fraction_bits() as u128)).bvadd(&kept);
+    let infinity = constant(ctx, format.infinity_bits());
+    let bits = bits.bvuge(&infinity).ite(&infinity, &bits);
+    let bits = is_zero.ite(&zero, &bits);
+
+    sign.ite(&bits.bvor(&constant(ctx, format.sign_bit())), &bits)
+}
+
+fn add<'a>(ctx: &'a z3::Context, format: Format, x: &Unpacked<'a>, y: &Unpacked<'a>) -> z3::Ast<'a> {
+    let zero = constant(ctx, 0);
+    let one = constant(ctx, 1);
+
+    // Let `a` be the operand with the larger exponent. Both significands get
+    // enough room below them that only bits of `b` that lie well below the
+    // rounding position can be shifted out.
+    let room = format.significand_bits + 3;
+    let swap = y.exponent.bvsgt(&x.exponent);
+    let (a, b) = (swap.ite(&y.significand, &x.significand), swap.ite(&x.significand, &y.significand));
+    let (a_sign, b_sign) = (swap.ite(&y.sign, &x.sign), swap.ite(&x.sign, &y.sign));
+    let a_exponent = swap.ite(&y.exponent, &x.exponent);
+    let distance = a_exponent.bvsub(&swap.ite(&x.exponent, &y.exponent));
+    let distance = distance.bvugt(&constant(ctx, WIDTH as u128)).ite(&constant(ctx, WIDTH as u128), &distance);
+
+    let a = a.bvshl(&constant(ctx, room as u128));
+    let b = b.bvshl(&constant(ctx, room as u128));
+    let lost = low_bits(ctx, &b, &distance)._eq(&zero).not();
+    let b = b.bvlshr(&distance).bvor(&lost.ite(&one, &zero));
+
+    let same_sign = a_sign._eq(&b_sign);
+    let a_larger = a.bvuge(&b);
+    let sum = same_sign.ite(&a.bvadd(&b), &a_larger.ite(&a.bvsub(&b), &b.bvsub(&a)));
+    let sign = same_sign.ite(&a_sign, &a_larger.ite(&a_sign, &b_sign));
+
+    // An exact zero is positive, unless both operands are negative zeros.
+    let sign = sum._eq(&zero).ite(&a_sign.and(&[&b_sign]), &sign);
+    let finite = round(ctx, format, &sign, a_exponent.bvsub(&constant(ctx, room as u128)), sum);
+
+    let nan_result = x.nan.or(&[&y.nan, &x.infinite.and(&[&y.infinite, &x.sign._eq(&y.sign).not()])]);
+    let infinity = format.infinity_bits();
+    nan_result.ite(
+        &nan(ctx, format),
+        &x.infinite.ite(
+            &with_sign(ctx, format, &x.sign, infinity),
+            &y.infinite.ite(&with_sign(ctx, format, &y.sign, infinity), &finite)))
+}
+
+fn mul<'a>(ctx: &'a z3::Context, format: Format, x: &Unpacked<'a>, y: &Unpacked<'a>) -> z3::Ast<'a> {
+    let sign = x.sign.xor(&y.sign);
+    let finite = round(
+        ctx, format, &sign, x.exponent.bvadd(&y.exponent), x.significand.bvmul(&y.significand));
+
+    let nan_result = x.nan.or(&[
+        &y.nan,
+        &x.infinite.and(&[&y.zero]),
+        &x.zero.and(&[&y.infinite]),
+    ]);
+    nan_result.ite(
+        &nan(ctx, format),
+        &x.infinite.or(&[&y.infinite]).ite(
+            &with_sign(ctx, format, &sign, format.infinity_bits()),
+            &finite))
+}
+
+/// `significand * 2^exponent` with a significand of exactly `significand_bits`
+/// bits. `significand` must not be zero.
+fn normalize_to<'a>(
+    ctx: &'a z3::Context,
+    format: Format,
+    exponent: &z3::Ast<'a>,
+    significand: z3::Ast<'a>)
+    -> (z3::Ast<'a>, z3::Ast<'a>)
+{
+    let (normalized, shift) = normalize(ctx, significand);
+    let low = constant(ctx, (WIDTH - format.significand_bits) as u128);
+    (exponent.bvsub(&shift.bvsub(&low)), normalized.bvlshr(&low))
+}
+
+fn div<'a>(ctx: &'a z3::Context, format: Format, x: &Unpacked<'a>, y: &Unpacked<'a>) -> z3::Ast<'a> {
+    let zero = constant(ctx, 0);
+    let one = constant(ctx, 1);
+    let sign = x.sign.xor(&y.sign);
+
+    // With both significands normalized, the quotient has at least
+    // `significand_bits + 2` bits, so the remainder only decides the sticky bit.
+    let extra = format.significand_bits + 2;
+    let (x_exponent, x_significand) = normalize_to(ctx, format, &x.exponent, x.significand.clone());
+    let (y_exponent, y_significand) = normalize_to(ctx, format, &y.exponent, y.significand.clone());
+    let dividend = x_significand.bvshl(&constant(ctx, extra as u128));
+    let quotient = dividend.bvudiv(&y_significand);
+    let inexact = dividend.bvurem(&y_significand)._eq(&zero).not();
+    let finite = round(
+        ctx, format, &sign,
+        x_exponent.bvsub(&y_exponent).bvsub(&constant(ctx, extra as u128)),
+        quotient.bvor(&inexact.ite(&one, &zero)));
+
+    let nan_result = x.nan.or(&[
+        &y.nan,
+        &x.zero.and(&[&y.zero]),
+        &x.infinite.and(&[&y.infinite]),
+    ]);
+    nan_result.ite(
+        &nan(ctx, format),
+        &x.infinite.or(&[&y.zero]).ite(
+            &with_sign(ctx, format, &sign, format.infinity_bits()),
+            &x.zero.or(&[&y.infinite]).ite(&with_sign(ctx, format, &sign, 0), &finite)))
+}
+
+/// `2^exponent mod modulus`, for an `exponent` below `2^bits`.
+fn pow2_mod<'a>(
+    ctx: &'a z3::Context,
+    exponent: &z3::Ast<'a>,
+    bits: u32,
+    modulus: &z3::Ast<'a>)
+    -> z3::Ast<'a>
+{
+    let mut result = constant(ctx, 1).bvurem(modulus);
+    let mut power = constant(ctx, 2).bvurem(modulus);
+    for bit in 0..bits {
+        let set = bit_is_set(ctx, exponent, &constant(ctx, bit as u128));
+        result = set.ite(&result.bvmul(&power).bvurem(modulus), &result);
+        power = power.bvmul(&power).bvurem(modulus);
+    }
+    result
+}
+
+/// The remainder of truncating division, like C's `fmod`. It is always exact.
+fn rem<'a>(
+    ctx: &'a z3::Context,
+    format: Format,
+    val: &z3::Ast<'a>,
+    x: &Unpacked<'a>,
+    y: &Unpacked<'a>)
+    -> z3::Ast<'a>
+{
+    // If `x` has the larger exponent, it is `x_significand * 2^distance` in units
+    // of `2^y.exponent`, and the distance is below `2^exponent_bits`.
+    let distance = x.exponent.bvsub(&y.exponent);
+    let x_first = x.significand.bvurem(&y.significand)
+        .bvmul(&pow2_mod(ctx, &distance, format.exponent_bits, &y.significand))
+        .bvurem(&y.significand);
+
+    // Otherwise `y` is `y_significand * 2^distance` in units of `2^x.exponent`,
+    // and larger than `x` if the distance is at least `significand_bits`.
+    let distance = y.exponent.bvsub(&x.exponent);
+    let y_first = distance.bvuge(&constant(ctx, format.significand_bits as u128)).ite(
+        &x.significand,
+        &x.significand.bvurem(&y.significand.bvshl(&distance)));
+
+    let x_larger = x.exponent.bvsge(&y.exponent);
+    let finite = round(
+        ctx, format, &x.sign,
+        x_larger.ite(&y.exponent, &x.exponent),
+        x_larger.ite(&x_first, &y_first));
+
+    x.nan.or(&[&y.nan, &x.infinite, &y.zero]).ite(
+        &nan(ctx, format),
+        &y.infinite.or(&[&x.zero]).ite(val, &finite))
+}
+
+/// `left operator right` for the arithmetic operators. `left` and `right` are
+/// floats of `format`, zero-extended to `WIDTH` bits.
+pub fn binop_to_ast<'a, 'tcx>(
+    ctx: &'a z3::Context,
+    format: Format,
+    operator: mir::BinOp,
+    left: &z3::Ast<'a>,
+    right: &z3::Ast<'a>)
+    -> EvalResult<'tcx, z3::Ast<'a>>
+{
+    let x = unpack(ctx, format, left);
+    Ok(match operator {
+        mir::BinOp::Add => add(ctx, format, &x, &unpack(ctx, format, right)),
+        mir::BinOp::Sub => {
+            let negated = right.bvxor(&constant(ctx, format.sign_bit()));
+            add(ctx, format, &x, &unpack(ctx, format, &negated))
+        }
+        mir::BinOp::Mul => mul(ctx, format, &x, &unpack(ctx, format, right)),
+        mir::BinOp::Div => div(ctx, format, &x, &unpack(ctx, format, right)),
+        mir::BinOp::Rem => rem(ctx, format, left, &x, &unpack(ctx, format, right)),
+        _ => {
+            let msg = format!("abstract floating-point {:?}", operator);
+            return Err(EvalError::Unimplemented(msg));
+        }
+    })
+}
+
+/// Converts a float of format `from` to format `to`.
+pub fn float_to_float<'a>(ctx: &'a z3::Context, from: Format, to: Format, val: &z3::Ast<'a>) -> z3::Ast<'a> {
+    let x = unpack(ctx, from, val);
+    x.nan.ite(
+        &nan(ctx, to),
+        &x.infinite.ite(
+            &with_sign(ctx, to, &x.sign, to.infinity_bits()),
+            &round(ctx, to, &x.sign, x.exponent.clone(), x.significand.clone())))
+}
+
+/// Converts an integer of `bits` bits to the nearest float of format `to`.
+pub fn int_to_float<'a>(
+    ctx: &'a z3::Context,
+    signed: bool,
+    bits: u32,
+    to: Format,
+    val: &z3::Ast<'a>)
+    -> z3::Ast<'a>
+{
+    let sign = if signed {
+        bit_is_set(ctx, val, &constant(ctx, (bits - 1) as u128))
+    } else {
+        z3::Ast::from_bool(ctx, false)
+    };
+    let magnitude = sign.ite(&low_bits(ctx, &val.bvneg(), &constant(ctx, bits as u128)), val);
+    round(ctx, to, &sign, constant(ctx, 0), magnitude)
+}
+
+/// Converts a float of format `from` to an integer of `bits` bits the way that
+/// concrete casts do: the value is rounded toward zero and saturated to a
+/// 128-bit integer, which is then truncated. Negative values become zero when
+/// cast to unsigned integers, and so does NaN.
+pub fn float_to_int<'a>(
+    ctx: &'a z3::Context,
+    from: Format,
+    signed: bool,
+    bits: u32,
+    val: &z3::Ast<'a>)
+    -> z3::Ast<'a>
+{
+    let zero = constant(ctx, 0);
+    let x = unpack(ctx, from, val);
+
+    let too_large = x.infinite.or(&[
+        &x.exponent.bvsgt(&constant(ctx, (WIDTH - from.significand_bits) as u128)),
+    ]);
+    let magnitude = x.exponent.bvsge(&zero).ite(
+        &x.significand.bvshl(&x.exponent),
+        &x.significand.bvlshr(&x.exponent.bvneg()));
+    let limit = x.sign.ite(&constant(ctx, 1 << (WIDTH - 1)), &constant(ctx, !0));
+    let magnitude = too_large.or(&[&magnitude.bvugt(&limit)]).ite(&limit, &magnitude);
+
+    let result = if signed {
+        x.sign.ite(&magnitude.bvneg(), &magnitude)
+    } else {
+        x.sign.ite(&zero, &magnitude)
+    };
+    let result = x.nan.ite(&zero, &result);
+    low_bits(ctx, &result, &constant(ctx, bits as u128))
+}
@@ -17,9 +17,11 @@ extern crate rustc_const_math;
 extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_errors;
+extern crate serialize as rustc_serialize;
 extern crate syntax;
 
 // From crates.io.
+extern crate libc;
 extern crate seer_z3 as z3;
 
 pub mod byteorder;
@@ -28,9 +30,13 @@ mod constraints;
 mod error;
 mod eval_context;
 mod executor;
+mod float;
 mod lvalue;
 mod memory;
 mod operator;
+#[cfg(unix)]
+mod parallel;
+mod search;
 mod step;
 mod terminator;
 mod traits;
@@ -51,8 +57,10 @@ pub use eval_context::{
 };
 
 pub use executor::{
+    Budget,
     ExecutionComplete,
     ExecutionConfig,
+    ExecutionStatus,
 };
 
 pub use lvalue::{
@@ -60,6 +68,13 @@ pub use lvalue::{
     LvalueExtra,
 };
 
+pub use search::{
+    Coverage,
+    SearchStrategy,
+    SearchStrategyBuilder,
+    SearchStrategyKind,
+};
+
 pub use memory::{
     AllocId,
     Memory,
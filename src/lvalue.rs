@@ -3,9 +3,10 @@ use rustc::ty::layout::{Size, Align};
 use rustc::ty::{self, Ty};
 use rustc_data_structures::indexed_vec::Idx;
 
-use error::EvalResult;
+use constraints::Constraint;
+use error::{EvalError, EvalResult};
 use eval_context::{EvalContext};
-use memory::{Pointer, PointerOffset};
+use memory::{AllocId, Pointer, PointerOffset, SByte};
 use value::{PrimVal, PrimValKind, Value};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -32,7 +33,8 @@ pub enum Lvalue<'tcx> {
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum LvalueExtra {
     None,
-    Length(u64),
+    /// The length of a slice or `str`, which may be abstract.
+    Length(PrimVal),
     Vtable(Pointer),
     DowncastVariant(usize),
 }
@@ -79,9 +81,9 @@ impl<'tcx> Lvalue<'tcx> {
         ptr
     }
 
-    pub(super) fn elem_ty_and_len(self, ty: Ty<'tcx>) -> (Ty<'tcx>, u64) {
+    pub(super) fn elem_ty_and_len(self, ty: Ty<'tcx>) -> (Ty<'tcx>, PrimVal) {
         match ty.sty {
-            ty::TyArray(elem, n) => (elem, n as u64),
+            ty::TyArray(elem, n) => (elem, PrimVal::Bytes(n as u128)),
 
             ty::TySlice(elem) => {
                 match self {
@@ -107,11 +109,113 @@ impl<'tcx> Global<'tcx> {
 }
 
 impl<'a, 'tcx> EvalContext<'a, 'tcx> {
-    pub(super) fn eval_and_read_lvalue(&mut self, lvalue: &mir::Lvalue<'tcx>) -> EvalResult<'tcx, Value> {
-        let lvalue = self.eval_lvalue(lvalue)?;
+    pub(super) fn eval_and_read_lvalue(&mut self, mir_lvalue: &mir::Lvalue<'tcx>) -> EvalResult<'tcx, Value> {
+        let lvalue = self.eval_lvalue(mir_lvalue)?;
+        if let Lvalue::Ptr { ptr, extra: LvalueExtra::None } = lvalue {
+            if !ptr.is_concrete() {
+                let ty = self.lvalue_ty(mir_lvalue);
+                if let Some(ptr) = self.concretize_pointer_read(ptr, ty)? {
+                    return Ok(self.read_lvalue(Lvalue::from_ptr(ptr)));
+                }
+            }
+        }
         Ok(self.read_lvalue(lvalue))
     }
 
+    /// Relocations cannot have abstract offsets, so reading a value of type `ty` at the
+    /// abstract `ptr` would lose any pointers in it, such as the entries of a table of
+    /// function pointers. If the bytes that the read can reach hold pointers, this
+    /// splits off a path for each distinct value that the read could produce, and
+    /// returns a concrete location of the value that the current path reads. The other
+    /// paths execute the current statement again. Otherwise the read goes through the
+    /// array theory like any other read at an abstract offset.
+    fn concretize_pointer_read(&mut self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx, Option<Pointer>> {
+        let size = match self.type_size(ty)? {
+            Some(size) if size > 0 => size,
+            _ => return Ok(None),
+        };
+        let align = self.type_align(ty)?;
+
+        if self.memory.get(ptr.alloc_id)?.relocations.is_empty() {
+            return Ok(None);
+        }
+        let (low, high) = match self.memory.abstract_offset_range(ptr, size)? {
+            Some(range) => range,
+            None => return Ok(None),
+        };
+        if !self.memory.relocations_in(ptr.alloc_id, low, high + size)? {
+            return Ok(None);
+        }
+
+        // Every offset in the range is a candidate, including misaligned ones, whose
+        // paths go on to fail the alignment check of the concrete read. A pointer that
+        // starts before a candidate but overlaps it counts towards its contents, and so
+        // does the misalignment, so that offsets whose reads fail differently never
+        // share a path.
+        let candidates = {
+            let pointer_size = self.memory.pointer_size();
+            let alloc = self.memory.get(ptr.alloc_id)?;
+            let mut candidates = Vec::new();
+            for offset in low..(high + 1) {
+                let bytes = alloc.bytes[offset as usize .. (offset + size) as usize].to_vec();
+                let relocations: Vec<(i64, AllocId)> = alloc.relocations
+                    .range(offset.saturating_sub(pointer_size - 1) .. offset + size)
+                    .map(|(&k, &id)| (k as i64 - offset as i64, id))
+                    .collect();
+                candidates.push((offset, (offset % align, bytes, relocations)));
+            }
+            candidates
+        };
+
+        // Offsets that hold the same bytes and pointers share a path.
+        let abstract_offset = ptr.offset.to_primval();
+        let mut groups: Vec<((u64, Vec<SByte>, Vec<(i64, AllocId)>), Vec<u64>)> = Vec::new();
+        for (offset, contents) in candidates {
+            let at = [Constraint::new_compare(
+                mir::BinOp::Eq, PrimValKind::U64, abstract_offset, PrimVal::Bytes(offset as u128))];
+            if !self.memory.constraints.is_feasible_with(&at[..])? {
+                continue;
+            }
+            match groups.iter().position(|group| group.0 == contents) {
+                Some(idx) => groups[idx].1.push(offset),
+                None => groups.push((contents, vec![offset])),
+            }
+        }
+
+        let mut paths = Vec::new();
+        for (_, offsets) in groups {
+            let constraint = if offsets.len() == 1 {
+                Constraint::new_compare(
+                    mir::BinOp::Eq, PrimValKind::U64, abstract_offset, PrimVal::Bytes(offsets[0] as u128))
+            } else {
+                let mut selected = PrimVal::from_bool(false);
+                for &offset in &offsets {
+                    let at = self.memory.constraints.add_binop_constraint(
+                        mir::BinOp::Eq, abstract_offset, PrimVal::Bytes(offset as u128), PrimValKind::U64)?;
+                    selected = self.memory.constraints.add_binop_constraint(
+                        mir::BinOp::BitOr, selected, at, PrimValKind::Bool)?;
+                }
+                Constraint::new_compare(mir::BinOp::Eq, PrimValKind::Bool, selected, PrimVal::from_bool(true))
+            };
+            paths.push((constraint, offsets[0]));
+        }
+
+        let mut paths = paths.into_iter();
+        let (constraint, offset) = match paths.next() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        for (other_constraint, _) in paths {
+            let mut cx = self.clone();
+            cx.side_errors.clear();
+            cx.side_paths.clear();
+            cx.memory.constraints.push_constraint(other_constraint);
+            self.side_paths.push(cx);
+        }
+        self.memory.constraints.push_constraint(constraint);
+        Ok(Some(Pointer::new(ptr.alloc_id, offset)))
+    }
+
     pub fn read_lvalue(&self, lvalue: Lvalue<'tcx>) -> Value {
         match lvalue {
             Lvalue::Ptr { ptr, extra } => {
@@ -256,13 +360,13 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         };
 
         let ptr = match base_ptr.offset {
-            PointerOffset::Concrete(_) => base_ptr.offset(offset),
-            PointerOffset::Abstract(sbytes) => {
+            PointerOffset::Concrete(_) => base_ptr.offset(offset)?,
+            PointerOffset::Abstract(_) => {
                 let new_offset = self.memory.constraints.add_binop_constraint(
                     mir::BinOp::Add,
                     PrimVal::Bytes(offset as u128),
-                    PrimVal::Abstract(sbytes),
-                    PrimValKind::U64);
+                    base_ptr.offset.to_primval(),
+                    PrimValKind::U64)?;
                 if let PrimVal::Abstract(sb) = new_offset {
                     Pointer::new_abstract(base_ptr.alloc_id, sb)
                 } else {
@@ -275,7 +379,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
         if packed {
             let size = self.type_size(field_ty)?.expect("packed struct must be sized");
-            self.memory.mark_packed(ptr, size);
+            self.memory.mark_packed(ptr, size)?;
         }
 
         let extra = if self.type_is_sized(field_ty) {
@@ -360,47 +464,32 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let elem_size = self.type_size(elem_ty)?.expect("slice element must be sized");
                 let n_ptr = self.eval_operand(operand)?;
                 let usize = self.tcx.types.usize;
-                let primval = self.value_to_primval(n_ptr, usize)?;
-                if let PrimVal::Abstract(_sbytes) = primval {
-                    // byte_offset = sbytes * elem_size
-                    let byte_offset = self.memory.constraints.add_binop_constraint(
-                        mir::BinOp::Mul,
-                        primval,
-                        PrimVal::Bytes(elem_size as u128),
-                        PrimValKind::U64);
-
-                    if let PrimVal::Abstract(sbytes) = byte_offset {
-                        (Pointer::new_abstract(base_ptr.alloc_id, sbytes), LvalueExtra::None)
-                    } else {
-                        unreachable!()
-                    }
-                } else {
-                    let n = primval.to_u64()?;
-                    assert!(n < len);
-                    let ptr = base_ptr.offset(n * elem_size);
-                    (ptr, LvalueExtra::None)
-                }
+                let index = self.value_to_primval(n_ptr, usize)?;
+                self.check_in_bounds(mir::BinOp::Lt, index, len)?;
+                (self.index_ptr(base_ptr, index, elem_size)?, LvalueExtra::None)
             }
 
-            ConstantIndex { offset, min_length, from_end } => {
+            ConstantIndex { offset, from_end, .. } => {
                 let base = self.eval_lvalue(&proj.base)?;
                 let base_ty = self.lvalue_ty(&proj.base);
                 // FIXME(solson)
                 let base = self.force_allocation(base)?;
                 let (base_ptr, _) = base.to_ptr_and_extra();
 
-                let (elem_ty, n) = base.elem_ty_and_len(base_ty);
+                let (elem_ty, len) = base.elem_ty_and_len(base_ty);
                 let elem_size = self.type_size(elem_ty)?.expect("sequence element must be sized");
-                assert!(n >= min_length as u64);
+                let usize = self.tcx.types.usize;
+                let offset = PrimVal::Bytes(offset as u128);
 
+                // Counting from the end wraps around to an out-of-bounds index if
+                // `offset` is larger than the length.
                 let index = if from_end {
-                    n - u64::from(offset)
+                    self.binary_op(mir::BinOp::Sub, len, usize, offset, usize)?.0
                 } else {
-                    u64::from(offset)
+                    offset
                 };
-
-                let ptr = base_ptr.offset(index * elem_size);
-                (ptr, LvalueExtra::None)
+                self.check_in_bounds(mir::BinOp::Lt, index, len)?;
+                (self.index_ptr(base_ptr, index, elem_size)?, LvalueExtra::None)
             }
 
             Subslice { from, to } => {
@@ -410,18 +499,65 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let base = self.force_allocation(base)?;
                 let (base_ptr, _) = base.to_ptr_and_extra();
 
-                let (elem_ty, n) = base.elem_ty_and_len(base_ty);
+                let (elem_ty, len) = base.elem_ty_and_len(base_ty);
                 let elem_size = self.type_size(elem_ty)?.expect("slice element must be sized");
-                assert!(u64::from(from) <= n - u64::from(to));
-                let ptr = base_ptr.offset(u64::from(from) * elem_size);
-                let extra = LvalueExtra::Length(n - u64::from(to) - u64::from(from));
-                (ptr, extra)
+                let usize = self.tcx.types.usize;
+                let trimmed = PrimVal::Bytes(from as u128 + to as u128);
+                self.check_in_bounds(mir::BinOp::Le, trimmed, len)?;
+                let ptr = self.index_ptr(base_ptr, PrimVal::Bytes(from as u128), elem_size)?;
+                let len = self.binary_op(mir::BinOp::Sub, len, usize, trimmed, usize)?.0;
+                (ptr, LvalueExtra::Length(len))
             }
         };
 
         Ok(Lvalue::Ptr { ptr, extra })
     }
 
+    /// Requires `index bin_op len`, where `bin_op` is `Lt` or `Le`. If an abstract
+    /// `index` or `len` can violate that, the path where it does splits off as an
+    /// `ArrayIndexOutOfBounds` error.
+    fn check_in_bounds(&mut self, bin_op: mir::BinOp, index: PrimVal, len: PrimVal) -> EvalResult<'tcx> {
+        let span = self.current_span();
+        if index.is_concrete() && len.is_concrete() {
+            let (index, len) = (index.to_u64()?, len.to_u64()?);
+            let in_bounds = match bin_op {
+                mir::BinOp::Lt => index < len,
+                _ => index <= len,
+            };
+            return if in_bounds {
+                Ok(())
+            } else {
+                Err(EvalError::ArrayIndexOutOfBounds(span, len, index))
+            };
+        }
+
+        let in_bounds = self.memory.constraints.add_binop_constraint(
+            bin_op, index, len, PrimValKind::U64)?;
+        self.require_valid(in_bounds, move |this, fails| {
+            let len = this.witness_u64(len, fails)?;
+            let index = this.witness_u64(index, fails)?;
+            Ok(EvalError::ArrayIndexOutOfBounds(span, len, index))
+        })
+    }
+
+    /// The pointer to element `index` of the sequence at `base_ptr`. Its offset is
+    /// abstract if `base_ptr` or `index` is.
+    fn index_ptr(&mut self, base_ptr: Pointer, index: PrimVal, elem_size: u64) -> EvalResult<'tcx, Pointer> {
+        if base_ptr.is_concrete() && index.is_concrete() {
+            return base_ptr.offset(index.to_u64()? * elem_size);
+        }
+
+        let usize = self.tcx.types.usize;
+        let byte_offset = self.binary_op(
+            mir::BinOp::Mul, index, usize, PrimVal::Bytes(elem_size as u128), usize)?.0;
+        let offset = self.binary_op(
+            mir::BinOp::Add, base_ptr.offset.to_primval(), usize, byte_offset, usize)?.0;
+        match offset {
+            PrimVal::Abstract(sbytes) => Ok(Pointer::new_abstract(base_ptr.alloc_id, sbytes)),
+            _ => Ok(Pointer::new(base_ptr.alloc_id, offset.to_u64()?)),
+        }
+    }
+
     pub(super) fn lvalue_ty(&self, lvalue: &mir::Lvalue<'tcx>) -> Ty<'tcx> {
         self.monomorphize(lvalue.ty(&self.mir(), self.tcx).to_ty(self.tcx), self.substs())
     }
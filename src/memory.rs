@@ -4,8 +4,9 @@ use std::{fmt, iter, ptr, mem, io};
 
 use rustc::{ty, mir};
 use rustc::ty::layout::{self, TargetDataLayout};
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
 
-use constraints::ConstraintContext;
+use constraints::{Constraint, ConstraintContext};
 use error::{EvalError, EvalResult};
 use value::{PrimVal, PrimValKind};
 
@@ -13,7 +14,7 @@ use value::{PrimVal, PrimValKind};
 // Allocations and pointers
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, RustcEncodable, RustcDecodable)]
 pub struct AllocId(pub u64);
 
 impl fmt::Display for AllocId {
@@ -22,10 +23,10 @@ impl fmt::Display for AllocId {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, RustcEncodable, RustcDecodable)]
 pub struct AbstractVariable(pub u32);
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, RustcEncodable, RustcDecodable)]
 pub enum SByte {
     Concrete(u8),
     Abstract(AbstractVariable),
@@ -47,6 +48,11 @@ pub struct Allocation {
     /// The alignment of the allocation to detect unaligned reads.
     pub align: u64,
 
+    /// The size that the program asked for, if it is abstract. `bytes` then has
+    /// room for the largest size that the path allows, and only accesses within
+    /// this size are in bounds.
+    pub abstract_size: Option<PrimVal>,
+
     /// Whether the allocation may be modified.
     /// Use the `mark_static_initalized` method of `Memory` to ensure that an error occurs, if the memory of this
     /// allocation is modified or deallocated in the future.
@@ -63,13 +69,13 @@ pub enum StaticKind {
     Immutable,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, RustcEncodable, RustcDecodable)]
 pub struct Pointer {
     pub alloc_id: AllocId,
     pub offset: PointerOffset,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum PointerOffset {
     /// Offset in bytes.
     Concrete(u64),
@@ -78,12 +84,67 @@ pub enum PointerOffset {
     Abstract([SByte; 8]),
 }
 
+// `serialize` has no impls for fixed-size arrays, so the abstract offset goes
+// through a `Vec`.
+impl Encodable for PointerOffset {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_enum("PointerOffset", |s| match *self {
+            PointerOffset::Concrete(offset) =>
+                s.emit_enum_variant("Concrete", 0, 1, |s| {
+                    s.emit_enum_variant_arg(0, |s| offset.encode(s))
+                }),
+            PointerOffset::Abstract(ref bytes) =>
+                s.emit_enum_variant("Abstract", 1, 1, |s| {
+                    s.emit_enum_variant_arg(0, |s| bytes[..].encode(s))
+                }),
+        })
+    }
+}
+
+impl Decodable for PointerOffset {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Self, D::Error> {
+        d.read_enum("PointerOffset", |d| {
+            d.read_enum_variant(&["Concrete", "Abstract"], |d, idx| match idx {
+                0 => Ok(PointerOffset::Concrete(d.read_enum_variant_arg(0, Decodable::decode)?)),
+                1 => {
+                    let v: Vec<SByte> = d.read_enum_variant_arg(0, Decodable::decode)?;
+                    if v.len() != 8 {
+                        return Err(d.error("abstract pointer offset must have 8 bytes"));
+                    }
+                    let mut bytes = [SByte::Concrete(0); 8];
+                    bytes.copy_from_slice(&v);
+                    Ok(PointerOffset::Abstract(bytes))
+                }
+                _ => Err(d.error("invalid PointerOffset variant")),
+            })
+        })
+    }
+}
+
+impl PointerOffset {
+    /// The offset as a `PrimVal` of kind `U64`.
+    pub fn to_primval(self) -> PrimVal {
+        match self {
+            PointerOffset::Concrete(n) => PrimVal::Bytes(n as u128),
+            PointerOffset::Abstract(offset) => {
+                let mut sbytes = [SByte::Concrete(0); 16];
+                sbytes[..8].copy_from_slice(&offset);
+                PrimVal::Abstract(sbytes)
+            }
+        }
+    }
+}
+
 impl Pointer {
     pub fn new(alloc_id: AllocId, offset: u64) -> Self {
         Pointer { alloc_id, offset: PointerOffset::Concrete(offset), }
     }
 
-    pub fn new_abstract(alloc_id: AllocId, offset: [SByte; 8]) -> Self {
+    /// Creates a pointer whose offset is the abstract `U64` value with the bytes
+    /// `sbytes`.
+    pub fn new_abstract(alloc_id: AllocId, sbytes: [SByte; 16]) -> Self {
+        let mut offset = [SByte::Concrete(0); 8];
+        offset.copy_from_slice(&sbytes[..8]);
         Pointer { alloc_id, offset: PointerOffset::Abstract(offset), }
     }
 
@@ -94,7 +155,7 @@ impl Pointer {
         }
     }
 
-    pub fn signed_offset(self, i: i64) -> Self {
+    pub fn signed_offset<'tcx>(self, i: i64) -> EvalResult<'tcx, Self> {
         match self.offset {
             PointerOffset::Concrete(self_offset) => {
                 // FIXME: is it possible to over/underflow here?
@@ -102,21 +163,36 @@ impl Pointer {
                     // trickery to ensure that i64::min_value() works fine
                     // this formula only works for true negative values, it panics for zero!
                     let n = u64::max_value() - (i as u64) + 1;
-                    Pointer::new(self.alloc_id, self_offset - n)
+                    Ok(Pointer::new(self.alloc_id, self_offset - n))
                 } else {
                     self.offset(i as u64)
                 }
             }
-            _ => unimplemented!(),
+            PointerOffset::Abstract(_) =>
+                Err(EvalError::Unimplemented(
+                    format!("cannot offset pointer with abstract offset by {}", i))),
         }
     }
 
-    pub fn offset(self, i: u64) -> Self {
+    pub fn offset<'tcx>(self, i: u64) -> EvalResult<'tcx, Self> {
         match self.offset {
             PointerOffset::Concrete(offset) => {
-                Pointer::new(self.alloc_id, offset + i)
+                Ok(Pointer::new(self.alloc_id, offset + i))
             }
-            _ => unimplemented!(),
+            PointerOffset::Abstract(_) =>
+                Err(EvalError::Unimplemented(
+                    format!("cannot offset pointer with abstract offset by {}", i))),
+        }
+    }
+
+    /// Returns the offset of this pointer, or an `Unimplemented` error
+    /// if the offset is abstract.
+    pub fn concrete_offset<'tcx>(&self) -> EvalResult<'tcx, u64> {
+        match self.offset {
+            PointerOffset::Concrete(offset) => Ok(offset),
+            PointerOffset::Abstract(_) =>
+                Err(EvalError::Unimplemented(
+                    "operation on a pointer with an abstract offset".to_string())),
         }
     }
 
@@ -126,12 +202,7 @@ impl Pointer {
 
     pub fn to_int<'tcx>(&self) -> EvalResult<'tcx, u64> {
         match self.alloc_id {
-            NEVER_ALLOC_ID => {
-                match self.offset {
-                    PointerOffset::Concrete(offset) => Ok(offset),
-                    _ => unimplemented!(),
-                }
-            }
+            NEVER_ALLOC_ID => self.concrete_offset(),
             _ => Err(EvalError::ReadPointerAsBytes),
         }
     }
@@ -272,6 +343,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             relocations: BTreeMap::new(),
             undef_mask: UndefMask::new(size),
             align,
+            abstract_size: None,
             static_kind: StaticKind::NotStatic,
         };
         let id = self.next_id;
@@ -289,13 +361,23 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         self.allocate_inner(vec![SByte::Concrete(0); size as usize], align)
     }
 
+    /// Records that the program asked for `size` bytes at `ptr`, which was allocated
+    /// with room for every size that the path allows.
+    pub fn set_abstract_size(&mut self, ptr: Pointer, size: PrimVal) -> EvalResult<'tcx> {
+        if ptr.points_to_zst() {
+            return Ok(());
+        }
+        self.get_mut(ptr.alloc_id)?.abstract_size = match size {
+            PrimVal::Abstract(_) => Some(size),
+            _ => None,
+        };
+        Ok(())
+    }
+
     // TODO(solson): Track which allocations were returned from __rust_allocate and report an error
     // when reallocating/deallocating any others.
     pub fn reallocate(&mut self, ptr: Pointer, new_size: u64, align: u64) -> EvalResult<'tcx, Pointer> {
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let ptr_offset = ptr.concrete_offset()?;
 
         // TODO(solson): Report error about non-__rust_allocate'd pointer.
         if ptr_offset != 0 {
@@ -319,7 +401,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             alloc.undef_mask.grow(amount, false);
         } else if size > new_size {
             self.memory_usage -= size - new_size;
-            self.clear_relocations(ptr.offset(new_size), size - new_size)?;
+            self.clear_relocations(ptr.offset(new_size)?, size - new_size)?;
             let alloc = self.get_mut(ptr.alloc_id)?;
             // `as usize` is fine here, since it is smaller than `size`, which came from a usize
             alloc.bytes.truncate(new_size as usize);
@@ -332,10 +414,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
 
     // TODO(solson): See comment on `reallocate`.
     pub fn deallocate(&mut self, ptr: Pointer) -> EvalResult<'tcx> {
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let ptr_offset = ptr.concrete_offset()?;
 
         if ptr.points_to_zst() {
             return Ok(());
@@ -361,6 +440,16 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         Ok(())
     }
 
+    /// The maximum number of virtual bytes that may be allocated.
+    pub fn memory_size(&self) -> u64 {
+        self.memory_size
+    }
+
+    /// The number of virtual bytes allocated.
+    pub fn memory_usage(&self) -> u64 {
+        self.memory_usage
+    }
+
     pub fn pointer_size(&self) -> u64 {
         self.layout.pointer_size.bytes()
     }
@@ -370,10 +459,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
     }
 
     pub fn check_align(&self, ptr: Pointer, align: u64, len: u64) -> EvalResult<'tcx> {
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let ptr_offset = ptr.concrete_offset()?;
 
         let alloc = self.get(ptr.alloc_id)?;
         // check whether the memory was marked as packed
@@ -412,17 +498,15 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         }
     }
 
-    pub(crate) fn mark_packed(&mut self, ptr: Pointer, len: u64) {
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+    pub(crate) fn mark_packed(&mut self, ptr: Pointer, len: u64) -> EvalResult<'tcx> {
+        let ptr_offset = ptr.concrete_offset()?;
 
         self.packed.insert(Entry {
             alloc_id: ptr.alloc_id,
             packed_start: ptr_offset,
             packed_end: ptr_offset + len,
         });
+        Ok(())
     }
 
     pub(crate) fn clear_packed(&mut self) {
@@ -589,10 +673,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         let alloc = self.get(ptr.alloc_id)?;
         let allocation_size = alloc.bytes.len() as u64;
 
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let ptr_offset = ptr.concrete_offset()?;
 
         if ptr_offset + size > allocation_size {
             return Err(EvalError::PointerOutOfBounds { ptr, size, allocation_size });
@@ -614,10 +695,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         let allocation_size = alloc.bytes.len() as u64;
 
         assert_eq!(size as usize as u64, size);
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let ptr_offset = ptr.concrete_offset()?;
 
         if ptr_offset + size > allocation_size {
             return Err(EvalError::PointerOutOfBounds { ptr, size, allocation_size });
@@ -718,13 +796,18 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         Ok(())
     }
 
+    /// Copies `size` bytes from `src` to `dest`. Only the concrete bounds of the
+    /// allocations are checked here; callers check abstract sizes with
+    /// `EvalContext::check_abstract_size` first, which also narrows the abstract
+    /// offsets that `abstract_copy` considers.
     pub fn copy(&mut self, src: Pointer, dest: Pointer, size: u64, align: u64) -> EvalResult<'tcx> {
         if size == 0 {
             return Ok(());
         }
 
         if let PointerOffset::Abstract(_) = dest.offset {
-            unimplemented!()
+            return Err(EvalError::Unimplemented(
+                "copy to a pointer with an abstract offset".to_string()));
         }
 
         if let PointerOffset::Abstract(_) = src.offset {
@@ -758,19 +841,35 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                      -> EvalResult<'tcx>
     {
         if src.alloc_id == dest.alloc_id {
-            unimplemented!()
+            return Err(EvalError::Unimplemented(
+                "abstract copy within a single allocation".to_string()));
         }
 
         let arr = self.constraints.new_array();
         let mut constraints = Vec::new();
         {
             let src_alloc = self.get(src.alloc_id)?;
-            if !src_alloc.relocations.is_empty() {
-                unimplemented!()
+
+            // Pointers cannot be array elements, so only the bytes that the copy
+            // can reach go into the array, and those must not hold pointers.
+            let (start, end) = if src_alloc.relocations.is_empty() {
+                (0, src_alloc.bytes.len() as u64)
+            } else {
+                match self.abstract_offset_range(src, size)? {
+                    Some((low, high)) => (low, high + size),
+                    None => {
+                        let allocation_size = src_alloc.bytes.len() as u64;
+                        return Err(EvalError::PointerOutOfBounds { ptr: src, size, allocation_size });
+                    }
+                }
+            };
+            if self.relocations_in(src.alloc_id, start, end)? {
+                return Err(EvalError::Unimplemented(
+                    "abstract copy of memory containing pointers".to_string()));
             }
 
-            for idx in 0..src_alloc.bytes.len() {
-                constraints.push((PrimVal::Bytes(idx as u128), src_alloc.bytes[idx]));
+            for idx in start..end {
+                constraints.push((PrimVal::Bytes(idx as u128), src_alloc.bytes[idx as usize]));
             }
         }
         for (primval, sbyte) in constraints {
@@ -778,28 +877,78 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         }
 
         match (src.offset, dest.offset) {
-            (PointerOffset::Abstract(src_offset),
+            (PointerOffset::Abstract(_),
              PointerOffset::Concrete(_dest_offset)) => {
                 for idx in 0..size {
                     let abs_idx = self.constraints.add_binop_constraint(
                         mir::BinOp::Add,
                         PrimVal::Bytes(idx as u128),
-                        PrimVal::Abstract(src_offset),
-                        PrimValKind::U64);
+                        src.offset.to_primval(),
+                        PrimValKind::U64)?;
 
                     let sbyte = self.constraints.add_array_element_constraint(arr, abs_idx);
-                    self.get_bytes_mut(dest.offset(idx), 1, 1)?[0] = sbyte;
+                    self.get_bytes_mut(dest.offset(idx)?, 1, 1)?[0] = sbyte;
                 }
 
             }
-            _ => unimplemented!(),
+            _ => return Err(EvalError::Unimplemented(
+                "abstract copy to a pointer with an abstract offset".to_string())),
         }
 
         Ok(())
     }
 
+    /// The smallest and largest offsets that the abstract offset of `ptr` can take
+    /// on the current path with `size` bytes at it still in bounds, or `None` if
+    /// there are none. Bisection keeps this to a logarithmic number of queries.
+    pub fn abstract_offset_range(&self, ptr: Pointer, size: u64) -> EvalResult<'tcx, Option<(u64, u64)>> {
+        let allocation_size = self.get(ptr.alloc_id)?.bytes.len() as u64;
+        if size > allocation_size {
+            return Ok(None);
+        }
+        let last = allocation_size - size;
+
+        let offset = ptr.offset.to_primval();
+        let at_least = |n: u64| Constraint::new_compare(
+            mir::BinOp::Ge, PrimValKind::U64, offset, PrimVal::Bytes(n as u128));
+        let at_most = |n: u64| Constraint::new_compare(
+            mir::BinOp::Le, PrimValKind::U64, offset, PrimVal::Bytes(n as u128));
+
+        if !self.constraints.is_feasible_with(&[at_most(last)])? {
+            return Ok(None);
+        }
+
+        let (mut low, mut high) = (0, last);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.constraints.is_feasible_with(&[at_most(mid)])? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        let smallest = low;
+
+        high = last;
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            if self.constraints.is_feasible_with(&[at_least(mid), at_most(last)])? {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Ok(Some((smallest, low)))
+    }
+
+    /// Whether any pointer in the allocation overlaps the bytes from `start` up to `end`.
+    pub fn relocations_in(&self, alloc_id: AllocId, start: u64, end: u64) -> EvalResult<'tcx, bool> {
+        let start = start.saturating_sub(self.pointer_size() - 1);
+        Ok(self.get(alloc_id)?.relocations.range(start..end).next().is_some())
+    }
+
     pub fn read_c_str(&self, _ptr: Pointer) -> EvalResult<'tcx, &[u8]> {
-        unimplemented!()
+        Err(EvalError::Unimplemented("reading C strings".to_string()))
         /*
         let alloc = self.get(ptr.alloc_id)?;
         assert_eq!(ptr.offset as usize as u64, ptr.offset);
@@ -840,10 +989,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         let size = self.pointer_size();
         self.check_defined(ptr, size)?;
 
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let ptr_offset = ptr.concrete_offset()?;
         let alloc = self.get(ptr.alloc_id)?;
 
         let endianess = self.endianess();
@@ -864,7 +1010,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                 None => Ok(Pointer::new_abstract(NEVER_ALLOC_ID, sbytes)),
             }
         } else {
-            unimplemented!()
+            bug!("read_target_uint returned {:?}", offset_primval)
         }
     }
 
@@ -878,15 +1024,16 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                 }
                 Ok(())
             }
-            (PointerOffset::Abstract(sbytes),
+            (PointerOffset::Abstract(_),
              PointerOffset::Concrete(dest_offset)) => {
-                self.write_primval(dest, PrimVal::Abstract(sbytes), 8)?; // FIXME usize
+                self.write_primval(dest, ptr.offset.to_primval(), 8)?; // FIXME usize
                 if ptr.alloc_id != NEVER_ALLOC_ID {
                     self.get_mut(dest.alloc_id)?.relocations.insert(dest_offset, ptr.alloc_id);
                 }
                 Ok(())
             }
-            _ => unimplemented!(),
+            _ => Err(EvalError::Unimplemented(
+                "writing a pointer to a location with an abstract offset".to_string())),
         }
     }
 
@@ -925,7 +1072,8 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                         Ok(())
                     }
                     layout::Endian::Big => {
-                        unimplemented!()
+                        Err(EvalError::Unimplemented(
+                            "abstract values on big endian targets".to_string()))
                     }
                 }
             }
@@ -941,7 +1089,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
             SByte::Concrete(1) => Ok(PrimVal::from_bool(true)),
             SByte::Concrete(_) => Err(EvalError::InvalidBool),
             SByte::Abstract(AbstractVariable(v)) => {
-                let mut buffer = [SByte::Concrete(0); 8];
+                let mut buffer = [SByte::Concrete(0); 16];
                 buffer[0] = SByte::Abstract(AbstractVariable(v));
                 Ok(PrimVal::Abstract(buffer))
             }
@@ -1051,7 +1199,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                 SByte::Concrete(b) => bytes.push(b),
                 SByte::Abstract(_v) => {
                     // At least one byte is abstract.
-                    let mut result = [SByte::Concrete(0); 8];
+                    let mut result = [SByte::Concrete(0); 16];
                     match self.endianess() {
                         layout::Endian::Little => {
                             for (idx, sb1) in sbytes.iter().enumerate() {
@@ -1059,7 +1207,9 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                             }
                         }
                         layout::Endian::Big => {
-                            unimplemented!()
+                            for (idx, sb1) in sbytes.iter().rev().enumerate() {
+                                result[idx] = *sb1;
+                            }
                         }
                     }
                     return Ok(PrimVal::Abstract(result));
@@ -1115,10 +1265,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
     fn relocations(&self, ptr: Pointer, size: u64)
         -> EvalResult<'tcx, btree_map::Range<u64, AllocId>>
     {
-        let ptr_offset = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let ptr_offset = ptr.concrete_offset()?;
 
         let start = ptr_offset.saturating_sub(self.pointer_size() - 1);
         let end = ptr_offset + size;
@@ -1137,10 +1284,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
         if keys.is_empty() { return Ok(()); }
 
         // Find the start and end of the given range and its outermost relocations.
-        let start = match ptr.offset {
-            PointerOffset::Concrete(offset) => offset,
-            _ => unimplemented!(),
-        };
+        let start = ptr.concrete_offset()?;
         let end = start + size;
         let first = *keys.first().unwrap();
         let last = *keys.last().unwrap() + self.pointer_size();
@@ -1160,7 +1304,7 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
 
     fn check_relocation_edges(&self, ptr: Pointer, size: u64) -> EvalResult<'tcx> {
         let overlapping_start = self.relocations(ptr, 0)?.count();
-        let overlapping_end = self.relocations(ptr.offset(size), 0)?.count();
+        let overlapping_end = self.relocations(ptr.offset(size)?, 0)?.count();
         if overlapping_start + overlapping_end != 0 {
             return Err(EvalError::ReadPointerAsBytes);
         }
@@ -1180,7 +1324,8 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                 self.get_mut(dest.alloc_id)?.relocations.extend(relocations);
                 Ok(())
             }
-            _ => unimplemented!(),
+            _ => Err(EvalError::Unimplemented(
+                "copying relocations to or from an abstract offset".to_string())),
         }
     }
 }
@@ -1204,7 +1349,8 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                 }
                 Ok(())
             }
-            _ => unimplemented!(),
+            _ => Err(EvalError::Unimplemented(
+                "copying the undef mask to or from an abstract offset".to_string())),
         }
     }
 
@@ -1217,7 +1363,8 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                 }
                 Ok(())
             }
-            _ => unimplemented!(),
+            _ => Err(EvalError::Unimplemented(
+                "checking definedness at an abstract offset".to_string())),
         }
     }
 
@@ -1241,7 +1388,8 @@ impl<'a, 'tcx> Memory<'a, 'tcx> {
                     // nothing to do
                     Ok(())
                 } else {
-                    unimplemented!()
+                    Err(EvalError::Unimplemented(
+                        "changing definedness at an abstract offset".to_string()))
                 }
             }
         }
@@ -40,11 +40,49 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         dest: Lvalue<'tcx>,
         dest_ty: Ty<'tcx>,
     ) -> EvalResult<'tcx> {
-        let (val, overflowed) = self.binop_with_overflow(op, left, right)?;
-        let val = Value::ByValPair(val, PrimVal::from_bool(overflowed));
+        let left_ty    = self.operand_ty(left);
+        let right_ty   = self.operand_ty(right);
+        let left_val   = self.eval_operand_to_primval(left)?;
+        let right_val  = self.eval_operand_to_primval(right)?;
+        let (val, overflowed) = self.binary_op(op, left_val, left_ty, right_val, right_ty)?;
+        let overflowed = if left_val.is_concrete() && right_val.is_concrete() {
+            PrimVal::from_bool(overflowed)
+        } else {
+            self.abstract_overflow(op, left_val, left_ty, right_val, right_ty, overflowed)?
+        };
+        let val = Value::ByValPair(val, overflowed);
         self.write_value(val, dest, dest_ty)
     }
 
+    /// Returns an abstract boolean that is true exactly when `op` overflows on the
+    /// abstract operands. `overflowed` is what `binary_op()` reported.
+    fn abstract_overflow(
+        &mut self,
+        op: mir::BinOp,
+        left: PrimVal,
+        left_ty: Ty<'tcx>,
+        right: PrimVal,
+        right_ty: Ty<'tcx>,
+        overflowed: bool,
+    ) -> EvalResult<'tcx, PrimVal> {
+        let kind = self.ty_to_primval_kind(left_ty)?;
+        match op {
+            mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul if kind.is_int() => {
+                self.memory.constraints.add_overflow_constraint(op, left, right, kind)
+            }
+
+            // A shift overflows if the amount, read as unsigned, is not less than the
+            // bit width.
+            mir::BinOp::Shl | mir::BinOp::Shr if !right.is_concrete() => {
+                let right_kind = self.ty_to_primval_kind(right_ty)?;
+                let amount_kind = PrimValKind::from_uint_size(right_kind.num_bytes() as u64);
+                let bits = PrimVal::Bytes(kind.num_bytes() as u128 * 8);
+                self.memory.constraints.add_binop_constraint(mir::BinOp::Ge, right, bits, amount_kind)
+            }
+            _ => Ok(PrimVal::from_bool(overflowed)),
+        }
+    }
+
     /// Applies the binary operation `op` to the arguments and writes the result to the
     /// destination. Returns `true` if the operation overflowed.
     pub(super) fn intrinsic_overflowing(
@@ -268,60 +306,88 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         left: PrimVal,
         left_kind: PrimValKind,
         right: PrimVal,
-        mut right_kind: PrimValKind,
+        right_kind: PrimValKind,
     ) -> EvalResult<'tcx, (PrimVal, bool)> {
 
         // These ops can have an RHS with a different numeric type.
         if bin_op == mir::BinOp::Shl || bin_op == mir::BinOp::Shr {
-            match (left, right) {
-                (PrimVal::Abstract(abytes), PrimVal::Bytes(rn)) if rn % 8 == 0 => {
-                    let num_bytes = (rn / 8) as usize;
-                    match bin_op {
-                        mir::BinOp::Shl => {
-                            let mut buffer = [SByte::Concrete(0); 8];
-                            for idx in num_bytes .. 8 {
-                                buffer[idx] = abytes[idx - num_bytes];
+            return self.abstract_shift(bin_op, left, left_kind, right, right_kind);
+        }
+
+        if left_kind != right_kind {
+            let msg = format!("unimplemented binary op: {:?}, {:?}, {:?}", left, right, bin_op);
+            return Err(EvalError::Unimplemented(msg));
+        }
+        Ok((self.memory.constraints.add_binop_constraint(bin_op, left, right, left_kind)?, false))
+    }
+
+    /// Shifts `left` by `right`. Like the concrete shifts, this only uses the low bits of
+    /// the shift amount and reports an overflow if the amount is at least the bit width
+    /// of `left`. The overflow flag is only meaningful for a concrete amount; see
+    /// `abstract_overflow()` for an abstract one.
+    fn abstract_shift(
+        &mut self,
+        bin_op: mir::BinOp,
+        left: PrimVal,
+        left_kind: PrimValKind,
+        right: PrimVal,
+        right_kind: PrimValKind,
+    ) -> EvalResult<'tcx, (PrimVal, bool)> {
+        if !left_kind.is_int() || !right_kind.is_int() {
+            let msg = format!("abstract shift of {:?} by {:?}", left_kind, right_kind);
+            return Err(EvalError::Unimplemented(msg));
+        }
+
+        let num_bytes = left_kind.num_bytes();
+        let bits = num_bytes as u128 * 8;
+
+        let amount = match right {
+            PrimVal::Bytes(n) => {
+                // Shifting by whole bytes only moves bytes around.
+                match left {
+                    PrimVal::Abstract(abytes) if n < bits && n % 8 == 0 => {
+                        let shift_bytes = (n / 8) as usize;
+                        let mut buffer = [SByte::Concrete(0); 16];
+                        match bin_op {
+                            mir::BinOp::Shl => {
+                                for idx in shift_bytes .. num_bytes {
+                                    buffer[idx] = abytes[idx - shift_bytes];
+                                }
+                                return Ok((PrimVal::Abstract(buffer), false));
                             }
-                            return Ok((PrimVal::Abstract(buffer), false));
-                        }
-                        mir::BinOp::Shr => {
-                            if !left_kind.is_signed_int() {
-                                let mut buffer = [SByte::Concrete(0); 8];
-                                for idx in num_bytes .. 8 {
-                                    buffer[idx - num_bytes] = abytes[idx];
+                            mir::BinOp::Shr if !left_kind.is_signed_int() => {
+                                for idx in shift_bytes .. num_bytes {
+                                    buffer[idx - shift_bytes] = abytes[idx];
                                 }
                                 return Ok((PrimVal::Abstract(buffer), false));
                             }
+                            _ => (),
                         }
-                        _ => unimplemented!(),
                     }
+                    _ => (),
                 }
-                _ => (),
+                PrimVal::Bytes(n & (bits - 1))
             }
 
-            if right_kind.num_bytes() == left_kind.num_bytes() {
-                right_kind = left_kind;
-            } else if right_kind.num_bytes() < left_kind.num_bytes() {
-                right_kind = left_kind;
-            } else {
-                if let PrimVal::Bytes(n) = right {
-                    if n < 256 {
-                        // HACK
-                        right_kind = left_kind;
-                    } else {
-                        unimplemented!()
-                    }
-                } else {
-                    unimplemented!()
-                }
+            PrimVal::Abstract(rbytes) => {
+                // Bring the amount to the width of `left`. Only its low bits matter.
+                let mut buffer = [SByte::Concrete(0); 16];
+                let common = ::std::cmp::min(num_bytes, right_kind.num_bytes());
+                buffer[..common].copy_from_slice(&rbytes[..common]);
+                self.memory.constraints.add_binop_constraint(
+                    mir::BinOp::BitAnd, PrimVal::Abstract(buffer), PrimVal::Bytes(bits - 1),
+                    left_kind)?
             }
-        }
 
-        if left_kind != right_kind {
-            let msg = format!("unimplemented binary op: {:?}, {:?}, {:?}", left, right, bin_op);
-            return Err(EvalError::Unimplemented(msg));
-        }
-        Ok((self.memory.constraints.add_binop_constraint(bin_op, left, right, left_kind), false))
+            _ => bug!("expected an abstract or concrete shift amount, got {:?}", right),
+        };
+
+        let overflowed = match right {
+            PrimVal::Bytes(n) => n >= bits,
+            _ => false,
+        };
+        let val = self.memory.constraints.add_binop_constraint(bin_op, left, amount, left_kind)?;
+        Ok((val, overflowed))
     }
 
     fn ptr_ops(
@@ -382,28 +448,17 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         right: Pointer,
         _right_kind: PrimValKind,
     ) -> EvalResult<'tcx, (PrimVal, bool)> {
-        use rustc::mir::BinOp::*;
         use value::PrimValKind::*;
 
-        let left_offset_primval = match left.offset {
-            PointerOffset::Concrete(n) => PrimVal::Bytes(n as u128),
-            PointerOffset::Abstract(sbytes) => PrimVal::Abstract(sbytes),
-        };
-
-        let right_offset_primval = match right.offset {
-            PointerOffset::Concrete(n) => PrimVal::Bytes(n as u128),
-            PointerOffset::Abstract(sbytes) => PrimVal::Abstract(sbytes),
-        };
+        let left_offset_primval = left.offset.to_primval();
+        let right_offset_primval = right.offset.to_primval();
 
         if left.alloc_id != right.alloc_id {
-            if let Eq = bin_op {
-                unimplemented!()
-            } else {
-                unimplemented!()
-            }
+            let msg = format!("abstract pointer op {:?} across allocations", bin_op);
+            Err(EvalError::Unimplemented(msg))
         } else {
             let result = self.memory.constraints.add_binop_constraint(
-                bin_op, left_offset_primval, right_offset_primval, U64);
+                bin_op, left_offset_primval, right_offset_primval, U64)?;
             Ok((result, false))
         }
     }
@@ -423,20 +478,20 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         if offset < alloc.bytes.len() as u128 {
                             Ok(PrimVal::Ptr(Pointer::new(left.alloc_id, offset as u64)))
                         } else {
-                            unimplemented!()
+                            Err(EvalError::Unimplemented(
+                                "pointer arithmetic past the end of an allocation".to_string()))
                         }
                     }
-                    _ => unimplemented!(),
+                    _ => Err(EvalError::Unimplemented(
+                        "integer added to a pointer with an abstract offset".to_string())),
                 }
             }
-            Sub => {
-                unimplemented!()
-            }
             BitOr | BitAnd | BitXor => {
                 Err(EvalError::ReadPointerAsBytes)
             }
             _ => {
-                unimplemented!()
+                let msg = format!("binary op {:?} on a pointer and an integer", bin_op);
+                Err(EvalError::Unimplemented(msg))
             }
         }
     }
@@ -451,9 +506,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         use value::PrimValKind::*;
 
         if !val.is_concrete() {
-            return
-                Ok(self.memory.constraints.add_unop_constraint(
-                    un_op, val, val_kind))
+            return self.memory.constraints.add_unop_constraint(un_op, val, val_kind);
         }
 
         let bytes = val.to_bytes()?;
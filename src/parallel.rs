@@ -0,0 +1,208 @@
+//! Exploration split across forked worker processes.
+//!
+//! A `TyCtxt` cannot leave the rustc session that created it, so each worker is
+//! a separate process that compiles the program again and explores its share of
+//! the paths. Workers send their results back over a pipe, one JSON object per
+//! line, and finish with their `ExecutionStatus`.
+//!
+//! Workers are forked from the calling process, which therefore has to be
+//! single-threaded: a forked child only gets the forking thread, so a lock held
+//! by any other thread, like the allocator's or the one on stdout, would stay
+//! locked in the child forever.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::io::FromRawFd;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+
+use libc;
+use rustc_serialize::json;
+
+use error::StaticEvalError;
+use executor::{Budget, ExecutionComplete, ExecutionConfig, ExecutionStatus, Worker};
+
+/// What a worker sends to the parent process.
+#[derive(RustcEncodable, RustcDecodable)]
+enum Message {
+    /// An `ExecutionComplete`.
+    Complete {
+        input: Vec<u8>,
+        error: Option<StaticEvalError>,
+    },
+
+    /// The worker is done.
+    Finished(ExecutionStatus),
+}
+
+impl From<ExecutionComplete> for Message {
+    fn from(complete: ExecutionComplete) -> Self {
+        Message::Complete { input: complete.input, error: complete.result.err() }
+    }
+}
+
+/// Runs `count` workers and hands their results to `config`'s consumer.
+pub fn run_workers(config: &ExecutionConfig, count: usize, args: Vec<String>) -> ExecutionStatus {
+    // Split late enough that every worker gets several subtrees, so that one
+    // deep subtree does not leave the others idle.
+    let mut split_depth = 3;
+    while (1 << split_depth) < 8 * count {
+        split_depth += 1;
+    }
+
+    if let Some(threads) = thread_count() {
+        assert!(threads == 1,
+                "seer worker processes must be forked from a single-threaded process, \
+                 but this one has {} threads", threads);
+    }
+
+    // Fork every worker before starting the threads that read from them.
+    let mut pids = Vec::new();
+    let mut pipes = Vec::new();
+    for index in 0..count {
+        let worker = Worker { index, count, split_depth };
+        let (pid, pipe) = spawn_worker(config, worker, &args);
+        pids.push(pid);
+        pipes.push(pipe);
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut readers = Vec::new();
+    for (index, pipe) in pipes.into_iter().enumerate() {
+        let tx = tx.clone();
+        readers.push(thread::spawn(move || {
+            for line in BufReader::new(pipe).lines() {
+                match line {
+                    Ok(line) => if tx.send((index, line)).is_err() { break },
+                    Err(_) => break,
+                }
+            }
+        }));
+    }
+    drop(tx);
+
+    // The channel closes once every worker has closed its end of the pipe.
+    let mut paths = 0;
+    let mut stopped = None;
+    let mut status = ExecutionStatus::Complete;
+    let mut finished = vec![false; count];
+    for (index, line) in rx.iter() {
+        // A worker that dies in the middle of a line leaves a truncated message.
+        let message = match json::decode(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("ignoring malformed message from seer worker: {}", e);
+                continue;
+            }
+        };
+        match message {
+            Message::Complete { input, error } => {
+                let result = match error {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                };
+                if !config.consume(ExecutionComplete { input, result }) {
+                    stopped = Some(ExecutionStatus::Stopped);
+                    break;
+                }
+                paths += 1;
+                if config.max_paths_budget().map_or(false, |n| paths >= n) {
+                    stopped = Some(ExecutionStatus::Truncated(Budget::Paths));
+                    break;
+                }
+            }
+            Message::Finished(worker_status) => {
+                finished[index] = true;
+                if status == ExecutionStatus::Complete {
+                    status = worker_status;
+                }
+            }
+        }
+    }
+
+    if stopped.is_some() {
+        for &pid in &pids {
+            unsafe { libc::kill(pid, libc::SIGKILL); }
+        }
+    }
+    drop(rx);
+    for reader in readers {
+        let _ = reader.join();
+    }
+    // A worker that did not finish, for example because it panicked, leaves its
+    // share of the paths unexplored.
+    let mut failed = false;
+    for (index, pid) in pids.into_iter().enumerate() {
+        let mut exit_status = 0;
+        unsafe { libc::waitpid(pid, &mut exit_status, 0); }
+        if stopped.is_none() && (exit_status != 0 || !finished[index]) {
+            warn!("seer worker {} did not finish; its share of the paths is unexplored", pid);
+            failed = true;
+        }
+    }
+
+    match stopped {
+        Some(stopped) => stopped,
+        None if failed => ExecutionStatus::WorkerFailed,
+        None => status,
+    }
+}
+
+/// The number of threads in this process, where the platform can tell.
+fn thread_count() -> Option<usize> {
+    ::std::fs::read_dir("/proc/self/task").ok().map(|tasks| tasks.count())
+}
+
+/// Forks a process that runs `worker`. Returns its pid and the read end of the
+/// pipe that it writes its results to.
+fn spawn_worker(config: &ExecutionConfig, worker: Worker, args: &[String]) -> (libc::pid_t, File) {
+    let mut fds = [0 as libc::c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        panic!("could not create pipe for seer worker: {}", io::Error::last_os_error());
+    }
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        panic!("could not fork seer worker: {}", io::Error::last_os_error());
+    }
+
+    if pid == 0 {
+        unsafe { libc::close(fds[0]); }
+        let pipe = unsafe { File::from_raw_fd(fds[1]) };
+        let code = run_worker(config, worker, args.to_vec(), pipe);
+        let _ = io::stdout().flush();
+
+        // Skip destructors and exit handlers; they belong to the parent.
+        unsafe { libc::_exit(code) }
+    }
+
+    unsafe { libc::close(fds[1]); }
+    (pid, unsafe { File::from_raw_fd(fds[0]) })
+}
+
+fn run_worker(config: &ExecutionConfig, worker: Worker, args: Vec<String>, pipe: File) -> i32 {
+    let pipe = Rc::new(RefCell::new(pipe));
+    let pipe1 = pipe.clone();
+    let mut config = config.for_worker(worker);
+    config.consumer(move |complete| send(&mut *pipe1.borrow_mut(), &Message::from(complete)));
+
+    // A panic must not unwind into the caller's code, which is still on the stack
+    // of the forked process.
+    match panic::catch_unwind(AssertUnwindSafe(|| config.run(args))) {
+        Ok(status) => {
+            send(&mut *pipe.borrow_mut(), &Message::Finished(status));
+            0
+        }
+        Err(_) => 101,
+    }
+}
+
+/// Writes `message` to the parent. Returns `false` if the parent has gone away.
+fn send(pipe: &mut File, message: &Message) -> bool {
+    match json::encode(message) {
+        Ok(line) => writeln!(pipe, "{}", line).is_ok(),
+        Err(e) => panic!("could not encode message of seer worker: {}", e),
+    }
+}
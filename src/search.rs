@@ -0,0 +1,285 @@
+use std::collections::{HashSet, VecDeque};
+
+use rustc::hir::def_id::DefId;
+use rustc::mir;
+use rustc::ty::TyCtxt;
+
+use eval_context::EvalContext;
+
+/// The set of MIR basic blocks that have been executed on any path so far.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    blocks: HashSet<(DefId, mir::BasicBlock)>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage { blocks: HashSet::new() }
+    }
+
+    /// Marks the block that `ecx` is currently in as covered.
+    pub fn record(&mut self, ecx: &EvalContext) {
+        if let Some(block) = current_block(ecx) {
+            self.blocks.insert(block);
+        }
+    }
+
+    /// Whether the block that `ecx` is currently in has already been executed.
+    pub fn covers(&self, ecx: &EvalContext) -> bool {
+        match current_block(ecx) {
+            Some(block) => self.blocks.contains(&block),
+            None => true,
+        }
+    }
+
+    /// The number of distinct basic blocks covered.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+}
+
+fn current_block(ecx: &EvalContext) -> Option<(DefId, mir::BasicBlock)> {
+    ecx.stack().last().map(|frame| (frame.instance.def_id(), frame.block))
+}
+
+/// Decides in which order the `Executor` explores pending paths.
+///
+/// The executor keeps stepping a path until it branches or finishes, so a strategy
+/// is only consulted at branch points.
+pub trait SearchStrategy<'a, 'tcx: 'a> {
+    /// Adds a path to the set of pending paths.
+    fn push(&mut self, ecx: EvalContext<'a, 'tcx>);
+
+    /// Removes and returns the path that should be explored next. `coverage`
+    /// holds the blocks executed so far.
+    fn pop(&mut self, coverage: &Coverage) -> Option<EvalContext<'a, 'tcx>>;
+
+    /// The number of pending paths.
+    fn len(&self) -> usize;
+}
+
+/// Creates the search strategy for the exploration of a program; see
+/// `ExecutionConfig::custom_search_strategy`.
+pub type SearchStrategyBuilder =
+    for<'a, 'tcx> Fn(TyCtxt<'a, 'tcx, 'tcx>) -> Box<SearchStrategy<'a, 'tcx> + 'a>;
+
+/// Selects one of the built-in search strategies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchStrategyKind {
+    /// Explore the most recently created path first.
+    DepthFirst,
+
+    /// Explore the least recently created path first.
+    BreadthFirst,
+
+    /// Pick a random path, giving each branch point equal weight, like KLEE's
+    /// random-path selection. Paths with fewer branch points behind them are
+    /// therefore more likely to be picked. The value is the seed of the random
+    /// number generator.
+    RandomPath(u64),
+
+    /// Explore the path with the fewest path constraints first.
+    FewestConstraints,
+
+    /// Prefer paths that are about to execute a basic block that no path has
+    /// executed yet, like KLEE's covering-new heuristic. Falls back to depth-first
+    /// order when every pending path is in covered code.
+    CoveringNew,
+}
+
+impl Default for SearchStrategyKind {
+    fn default() -> Self {
+        SearchStrategyKind::DepthFirst
+    }
+}
+
+impl SearchStrategyKind {
+    pub(crate) fn build<'a, 'tcx: 'a>(self) -> Box<SearchStrategy<'a, 'tcx> + 'a> {
+        match self {
+            SearchStrategyKind::DepthFirst => Box::new(DepthFirst::new()),
+            SearchStrategyKind::BreadthFirst => Box::new(BreadthFirst::new()),
+            SearchStrategyKind::RandomPath(seed) => Box::new(RandomPath::new(seed)),
+            SearchStrategyKind::FewestConstraints => Box::new(FewestConstraints::new()),
+            SearchStrategyKind::CoveringNew => Box::new(CoveringNew::new()),
+        }
+    }
+}
+
+pub struct DepthFirst<'a, 'tcx: 'a> {
+    stack: Vec<EvalContext<'a, 'tcx>>,
+}
+
+impl<'a, 'tcx: 'a> DepthFirst<'a, 'tcx> {
+    pub fn new() -> Self {
+        DepthFirst { stack: Vec::new() }
+    }
+}
+
+impl<'a, 'tcx: 'a> SearchStrategy<'a, 'tcx> for DepthFirst<'a, 'tcx> {
+    fn push(&mut self, ecx: EvalContext<'a, 'tcx>) {
+        self.stack.push(ecx);
+    }
+
+    fn pop(&mut self, _coverage: &Coverage) -> Option<EvalContext<'a, 'tcx>> {
+        self.stack.pop()
+    }
+
+    fn len(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+pub struct BreadthFirst<'a, 'tcx: 'a> {
+    queue: VecDeque<EvalContext<'a, 'tcx>>,
+}
+
+impl<'a, 'tcx: 'a> BreadthFirst<'a, 'tcx> {
+    pub fn new() -> Self {
+        BreadthFirst { queue: VecDeque::new() }
+    }
+}
+
+impl<'a, 'tcx: 'a> SearchStrategy<'a, 'tcx> for BreadthFirst<'a, 'tcx> {
+    fn push(&mut self, ecx: EvalContext<'a, 'tcx>) {
+        self.queue.push_back(ecx);
+    }
+
+    fn pop(&mut self, _coverage: &Coverage) -> Option<EvalContext<'a, 'tcx>> {
+        self.queue.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+pub struct RandomPath<'a, 'tcx: 'a> {
+    paths: Vec<EvalContext<'a, 'tcx>>,
+    rng: XorShift,
+}
+
+impl<'a, 'tcx: 'a> RandomPath<'a, 'tcx> {
+    pub fn new(seed: u64) -> Self {
+        RandomPath { paths: Vec::new(), rng: XorShift::new(seed) }
+    }
+}
+
+impl<'a, 'tcx: 'a> SearchStrategy<'a, 'tcx> for RandomPath<'a, 'tcx> {
+    fn push(&mut self, ecx: EvalContext<'a, 'tcx>) {
+        self.paths.push(ecx);
+    }
+
+    fn pop(&mut self, _coverage: &Coverage) -> Option<EvalContext<'a, 'tcx>> {
+        let min_depth = match self.paths.iter().map(|ecx| ecx.branch_path.len()).min() {
+            Some(d) => d,
+            None => return None,
+        };
+
+        // Walking down the tree of branch points and flipping a coin at each of them
+        // reaches a path of depth `d` with probability proportional to `2^-d`.
+        let weights: Vec<f64> = self.paths.iter().map(|ecx| {
+            let d = ::std::cmp::min(ecx.branch_path.len() - min_depth, 1000) as i32;
+            0.5f64.powi(d)
+        }).collect();
+        let total: f64 = weights.iter().sum();
+
+        let mut target = self.rng.next_f64() * total;
+        let mut idx = weights.len() - 1;
+        for (i, w) in weights.iter().enumerate() {
+            if target < *w {
+                idx = i;
+                break;
+            }
+            target -= *w;
+        }
+        Some(self.paths.swap_remove(idx))
+    }
+
+    fn len(&self) -> usize {
+        self.paths.len()
+    }
+}
+
+pub struct FewestConstraints<'a, 'tcx: 'a> {
+    paths: Vec<EvalContext<'a, 'tcx>>,
+}
+
+impl<'a, 'tcx: 'a> FewestConstraints<'a, 'tcx> {
+    pub fn new() -> Self {
+        FewestConstraints { paths: Vec::new() }
+    }
+}
+
+impl<'a, 'tcx: 'a> SearchStrategy<'a, 'tcx> for FewestConstraints<'a, 'tcx> {
+    fn push(&mut self, ecx: EvalContext<'a, 'tcx>) {
+        self.paths.push(ecx);
+    }
+
+    fn pop(&mut self, _coverage: &Coverage) -> Option<EvalContext<'a, 'tcx>> {
+        // On ties, prefer the most recently pushed path.
+        let mut best: Option<(usize, usize)> = None;
+        for (idx, ecx) in self.paths.iter().enumerate() {
+            let n = ecx.memory.constraints.num_constraints();
+            match best {
+                Some((_, best_n)) if best_n < n => (),
+                _ => best = Some((idx, n)),
+            }
+        }
+        best.map(|(idx, _)| self.paths.remove(idx))
+    }
+
+    fn len(&self) -> usize {
+        self.paths.len()
+    }
+}
+
+pub struct CoveringNew<'a, 'tcx: 'a> {
+    paths: Vec<EvalContext<'a, 'tcx>>,
+}
+
+impl<'a, 'tcx: 'a> CoveringNew<'a, 'tcx> {
+    pub fn new() -> Self {
+        CoveringNew { paths: Vec::new() }
+    }
+}
+
+impl<'a, 'tcx: 'a> SearchStrategy<'a, 'tcx> for CoveringNew<'a, 'tcx> {
+    fn push(&mut self, ecx: EvalContext<'a, 'tcx>) {
+        self.paths.push(ecx);
+    }
+
+    fn pop(&mut self, coverage: &Coverage) -> Option<EvalContext<'a, 'tcx>> {
+        // Coverage only grows, so a path has to be checked again every time.
+        match self.paths.iter().rposition(|ecx| !coverage.covers(ecx)) {
+            Some(idx) => Some(self.paths.remove(idx)),
+            None => self.paths.pop(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.paths.len()
+    }
+}
+
+/// A small xorshift* generator, so that random search is reproducible from a seed.
+struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        XorShift { state: seed ^ 0x9e37_79b9_7f4a_7c15 | 1 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -4,12 +4,37 @@ use rustc::ty::layout::{Layout, Size, Align};
 use rustc::ty::subst::Substs;
 use rustc::ty::{self, Ty};
 
+use constraints::{BitCount, ConstraintContext};
 use error::{EvalError, EvalResult};
 use eval_context::EvalContext;
 use lvalue::{Lvalue, LvalueExtra};
-use memory::{Pointer, PointerOffset};
+use memory::Pointer;
 use value::{PrimVal, PrimValKind, Value};
 
+macro_rules! integer_intrinsic {
+    ($name:expr, $val:expr, $kind:expr, $method:ident $(, $arg:expr)*) => ({
+        let val = $val;
+        let bytes = val.to_bytes()?;
+
+        use value::PrimValKind::*;
+        let result_bytes = match $kind {
+            I8 => (bytes as i8).$method($($arg),*) as u128,
+            U8 => (bytes as u8).$method($($arg),*) as u128,
+            I16 => (bytes as i16).$method($($arg),*) as u128,
+            U16 => (bytes as u16).$method($($arg),*) as u128,
+            I32 => (bytes as i32).$method($($arg),*) as u128,
+            U32 => (bytes as u32).$method($($arg),*) as u128,
+            I64 => (bytes as i64).$method($($arg),*) as u128,
+            U64 => (bytes as u64).$method($($arg),*) as u128,
+            I128 => (bytes as i128).$method($($arg),*) as u128,
+            U128 => bytes.$method($($arg),*) as u128,
+            _ => bug!("invalid `{}` argument: {:?}", $name, val),
+        };
+
+        PrimVal::Bytes(result_bytes)
+    });
+}
+
 impl<'a, 'tcx> EvalContext<'a, 'tcx> {
     pub(super) fn call_intrinsic(
         &mut self,
@@ -46,7 +71,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             "arith_offset" => {
                 let ptr = arg_vals[0].read_ptr(&self.memory)?;
                 let offset = self.value_to_primval(arg_vals[1], isize)?.to_i128()?;
-                let new_ptr = ptr.signed_offset(offset as i64);
+                let new_ptr = ptr.signed_offset(offset as i64)?;
                 self.write_primval(dest, PrimVal::Ptr(new_ptr), dest_ty)?;
             }
 
@@ -137,7 +162,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 self.write_primval(Lvalue::from_ptr(ptr), val, ty)?;
             },
 
-            "breakpoint" => unimplemented!(), // halt miri
+            "breakpoint" => return Err(EvalError::Unimplemented("breakpoint intrinsic".to_string())),
 
             "copy" |
             "copy_nonoverlapping" => {
@@ -148,17 +173,26 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let src = arg_vals[0].read_ptr(&self.memory)?;
                 let dest = arg_vals[1].read_ptr(&self.memory)?;
                 let count = self.value_to_primval(arg_vals[2], usize)?.to_u64()?;
+                self.check_abstract_size(src, count * elem_size)?;
+                self.check_abstract_size(dest, count * elem_size)?;
                 self.memory.copy(src, dest, count * elem_size, elem_align)?;
             }
 
             "ctpop" |
             "cttz" |
+            "cttz_nonzero" |
             "ctlz" |
+            "ctlz_nonzero" |
             "bswap" => {
                 let ty = instance.substs.type_at(0);
                 let num = self.value_to_primval(arg_vals[0], ty)?;
                 let kind = self.ty_to_primval_kind(ty)?;
-                let num = numeric_intrinsic(intrinsic_name, num, kind)?;
+                if intrinsic_name.ends_with("_nonzero") {
+                    let nonzero = self.binary_op(mir::BinOp::Ne, num, ty, PrimVal::Bytes(0), ty)?.0;
+                    self.require_intrinsic(intrinsic_name, nonzero, "called on zero")?;
+                }
+                let name = intrinsic_name.trim_right_matches("_nonzero");
+                let num = numeric_intrinsic(name, num, kind, &mut self.memory.constraints)?;
                 self.write_primval(dest, num, ty)?;
             }
 
@@ -166,53 +200,77 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let ty = instance.substs.type_at(0);
                 let adt_ptr = arg_vals[0].read_ptr(&self.memory)?;
                 let discr_val = self.read_discriminant_value(adt_ptr, ty)?;
-                self.write_primval(dest, PrimVal::Bytes(discr_val), dest_ty)?;
+                self.write_primval(dest, discr_val, dest_ty)?;
+            }
+
+            "exact_div" |
+            "unchecked_div" |
+            "unchecked_rem" |
+            "unchecked_shl" |
+            "unchecked_shr" => {
+                let ty = instance.substs.type_at(0);
+                let l = self.value_to_primval(arg_vals[0], ty)?;
+                let r = self.value_to_primval(arg_vals[1], ty)?;
+                let result = self.unchecked_intrinsic(intrinsic_name, l, r, ty)?;
+                self.write_primval(dest, result, ty)?;
             }
 
             "sinf32" | "fabsf32" | "cosf32" |
             "sqrtf32" | "expf32" | "exp2f32" |
             "logf32" | "log10f32" | "log2f32" |
             "floorf32" | "ceilf32" | "truncf32" => {
-                let f = self.value_to_primval(arg_vals[0], f32)?.to_f32()?;
-                let f = match intrinsic_name {
-                    "sinf32" => f.sin(),
-                    "fabsf32" => f.abs(),
-                    "cosf32" => f.cos(),
-                    "sqrtf32" => f.sqrt(),
-                    "expf32" => f.exp(),
-                    "exp2f32" => f.exp2(),
-                    "logf32" => f.ln(),
-                    "log10f32" => f.log10(),
-                    "log2f32" => f.log2(),
-                    "floorf32" => f.floor(),
-                    "ceilf32" => f.ceil(),
-                    "truncf32" => f.trunc(),
-                    _ => bug!(),
+                let val = self.value_to_primval(arg_vals[0], f32)?;
+                let result = if val.is_concrete() {
+                    let f = val.to_f32()?;
+                    let f = match intrinsic_name {
+                        "sinf32" => f.sin(),
+                        "fabsf32" => f.abs(),
+                        "cosf32" => f.cos(),
+                        "sqrtf32" => f.sqrt(),
+                        "expf32" => f.exp(),
+                        "exp2f32" => f.exp2(),
+                        "logf32" => f.ln(),
+                        "log10f32" => f.log10(),
+                        "log2f32" => f.log2(),
+                        "floorf32" => f.floor(),
+                        "ceilf32" => f.ceil(),
+                        "truncf32" => f.trunc(),
+                        _ => bug!(),
+                    };
+                    PrimVal::from_f32(f)
+                } else {
+                    self.abstract_fabs(intrinsic_name, val, PrimValKind::U32, 0x7fff_ffff)?
                 };
-                self.write_primval(dest, PrimVal::from_f32(f), dest_ty)?;
+                self.write_primval(dest, result, dest_ty)?;
             }
 
             "sinf64" | "fabsf64" | "cosf64" |
             "sqrtf64" | "expf64" | "exp2f64" |
             "logf64" | "log10f64" | "log2f64" |
             "floorf64" | "ceilf64" | "truncf64" => {
-                let f = self.value_to_primval(arg_vals[0], f64)?.to_f64()?;
-                let f = match intrinsic_name {
-                    "sinf64" => f.sin(),
-                    "fabsf64" => f.abs(),
-                    "cosf64" => f.cos(),
-                    "sqrtf64" => f.sqrt(),
-                    "expf64" => f.exp(),
-                    "exp2f64" => f.exp2(),
-                    "logf64" => f.ln(),
-                    "log10f64" => f.log10(),
-                    "log2f64" => f.log2(),
-                    "floorf64" => f.floor(),
-                    "ceilf64" => f.ceil(),
-                    "truncf64" => f.trunc(),
-                    _ => bug!(),
+                let val = self.value_to_primval(arg_vals[0], f64)?;
+                let result = if val.is_concrete() {
+                    let f = val.to_f64()?;
+                    let f = match intrinsic_name {
+                        "sinf64" => f.sin(),
+                        "fabsf64" => f.abs(),
+                        "cosf64" => f.cos(),
+                        "sqrtf64" => f.sqrt(),
+                        "expf64" => f.exp(),
+                        "exp2f64" => f.exp2(),
+                        "logf64" => f.ln(),
+                        "log10f64" => f.log10(),
+                        "log2f64" => f.log2(),
+                        "floorf64" => f.floor(),
+                        "ceilf64" => f.ceil(),
+                        "truncf64" => f.trunc(),
+                        _ => bug!(),
+                    };
+                    PrimVal::from_f64(f)
+                } else {
+                    self.abstract_fabs(intrinsic_name, val, PrimValKind::U64, 0x7fff_ffff_ffff_ffff)?
                 };
-                self.write_primval(dest, PrimVal::from_f64(f), dest_ty)?;
+                self.write_primval(dest, result, dest_ty)?;
             }
 
             "fadd_fast" | "fsub_fast" | "fmul_fast" | "fdiv_fast" | "frem_fast" => {
@@ -302,10 +360,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     let result_ptr = self.pointer_offset(ptr, substs.type_at(0), offset)?;
                     self.write_primval(dest, PrimVal::Ptr(result_ptr), dest_ty)?;
                 } else {
-                    let ptr_offset_primval = match ptr.offset {
-                        PointerOffset::Concrete(n) => PrimVal::Bytes(n as u128),
-                        PointerOffset::Abstract(sbytes) => PrimVal::Abstract(sbytes),
-                    };
+                    let ptr_offset_primval = ptr.offset.to_primval();
                     // need to account for the size of the type.
                     let size =
                         self.type_size(substs.type_at(0))?.expect(
@@ -315,12 +370,12 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         mir::BinOp::Mul,
                         PrimVal::Bytes(size as u128),
                         offset_primval,
-                        PrimValKind::U64);
+                        PrimValKind::U64)?;
                     let new_offset = self.memory.constraints.add_binop_constraint(
                         mir::BinOp::Add,
                         ptr_offset_primval,
                         byte_offset,
-                        PrimValKind::U64);
+                        PrimValKind::U64)?;
                     if let PrimVal::Abstract(sbytes) = new_offset {
                         let new_ptr = Pointer::new_abstract(ptr.alloc_id, sbytes);
                         self.write_primval(dest, PrimVal::Ptr(new_ptr), dest_ty)?;
@@ -380,6 +435,16 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 self.write_primval(dest, PrimVal::from_f64(f.powi(i as i32)), dest_ty)?;
             }
 
+            "rotate_left" |
+            "rotate_right" => {
+                let ty = instance.substs.type_at(0);
+                let val = self.value_to_primval(arg_vals[0], ty)?;
+                let amount = self.value_to_primval(arg_vals[1], ty)?;
+                let kind = self.ty_to_primval_kind(ty)?;
+                let result = self.rotate_intrinsic(intrinsic_name, val, amount, kind)?;
+                self.write_primval(dest, result, ty)?;
+            }
+
             "size_of" => {
                 let ty = instance.substs.type_at(0);
                 let size =
@@ -390,7 +455,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             "size_of_val" => {
                 let ty = instance.substs.type_at(0);
                 let (size, _) = self.size_and_align_of_dst(ty, arg_vals[0])?;
-                self.write_primval(dest, PrimVal::from_u128(size as u128), dest_ty)?;
+                self.write_primval(dest, size, dest_ty)?;
             }
 
             "min_align_of_val" |
@@ -420,7 +485,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let size = self.type_size(dest_ty)?.expect("transmute() type must be sized");
                 if dest_align < src_align {
                     let ptr = self.force_allocation(dest)?.to_ptr();
-                    self.memory.mark_packed(ptr, size);
+                    self.memory.mark_packed(ptr, size)?;
                     self.write_value_to_ptr(arg_vals[0], ptr, dest_ty)?;
                 } else {
                     self.write_value(arg_vals[0], dest, dest_ty)?;
@@ -456,6 +521,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let ptr = arg_vals[0].read_ptr(&self.memory)?;
                 let count = self.value_to_primval(arg_vals[2], usize)?.to_u64()?;
                 self.memory.check_align(ptr, ty_align, size * count)?;
+                self.check_abstract_size(ptr, size * count)?;
                 self.memory.write_repeat(ptr, val_byte, size * count)?;
             }
 
@@ -470,13 +536,145 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         Ok(())
     }
 
+    /// `fabs` of an abstract float, which clears the sign bit. Other float
+    /// intrinsics are not supported on abstract values.
+    fn abstract_fabs(
+        &mut self,
+        intrinsic_name: &str,
+        val: PrimVal,
+        bits_kind: PrimValKind,
+        mask: u128,
+    ) -> EvalResult<'tcx, PrimVal> {
+        if !intrinsic_name.starts_with("fabs") {
+            let msg = format!("{} of an abstract float", intrinsic_name);
+            return Err(EvalError::Unimplemented(msg));
+        }
+        self.memory.constraints.add_binop_constraint(
+            mir::BinOp::BitAnd, val, PrimVal::Bytes(mask), bits_kind)
+    }
+
+    /// Rotates `val` by `amount` modulo the bit width. Abstract values are rotated by
+    /// combining two logical shifts.
+    fn rotate_intrinsic(
+        &mut self,
+        name: &str,
+        val: PrimVal,
+        amount: PrimVal,
+        kind: PrimValKind,
+    ) -> EvalResult<'tcx, PrimVal> {
+        let bits = kind.num_bytes() as u128 * 8;
+        if let (PrimVal::Bytes(_), PrimVal::Bytes(n)) = (val, amount) {
+            let n = (n & (bits - 1)) as u32;
+            return Ok(match name {
+                "rotate_left" => integer_intrinsic!(name, val, kind, rotate_left, n),
+                _ => integer_intrinsic!(name, val, kind, rotate_right, n),
+            });
+        }
+
+        if !kind.is_int() {
+            let msg = format!("{} of {:?}", name, kind);
+            return Err(EvalError::Unimplemented(msg));
+        }
+
+        let kind = PrimValKind::from_uint_size(kind.num_bytes() as u64);
+        let constraints = &mut self.memory.constraints;
+        let (amount, back) = match amount {
+            PrimVal::Bytes(n) => {
+                let n = n & (bits - 1);
+                (PrimVal::Bytes(n), PrimVal::Bytes((bits - n) & (bits - 1)))
+            }
+            _ => {
+                let n = constraints.add_binop_constraint(
+                    mir::BinOp::BitAnd, amount, PrimVal::Bytes(bits - 1), kind)?;
+                let back = constraints.add_binop_constraint(
+                    mir::BinOp::Sub, PrimVal::Bytes(bits), n, kind)?;
+                let back = constraints.add_binop_constraint(
+                    mir::BinOp::BitAnd, back, PrimVal::Bytes(bits - 1), kind)?;
+                (n, back)
+            }
+        };
+
+        let (first, second) = match name {
+            "rotate_left" => (mir::BinOp::Shl, mir::BinOp::Shr),
+            _ => (mir::BinOp::Shr, mir::BinOp::Shl),
+        };
+        let high = constraints.add_binop_constraint(first, val, amount, kind)?;
+        let low = constraints.add_binop_constraint(second, val, back, kind)?;
+        constraints.add_binop_constraint(mir::BinOp::BitOr, high, low, kind)
+    }
+
+    /// `exact_div` and the `unchecked_*` intrinsics. These are undefined behavior
+    /// where the checked operations would panic, and `exact_div` also where the
+    /// division leaves a remainder.
+    fn unchecked_intrinsic(
+        &mut self,
+        name: &str,
+        l: PrimVal,
+        r: PrimVal,
+        ty: Ty<'tcx>,
+    ) -> EvalResult<'tcx, PrimVal> {
+        let kind = self.ty_to_primval_kind(ty)?;
+        let bits = kind.num_bytes() as u128 * 8;
+        let bin_op = match name {
+            "exact_div" | "unchecked_div" => mir::BinOp::Div,
+            "unchecked_rem" => mir::BinOp::Rem,
+            "unchecked_shl" => mir::BinOp::Shl,
+            "unchecked_shr" => mir::BinOp::Shr,
+            _ => bug!("not an unchecked intrinsic: {}", name),
+        };
+
+        if bin_op == mir::BinOp::Div || bin_op == mir::BinOp::Rem {
+            let nonzero = self.binary_op(mir::BinOp::Ne, r, ty, PrimVal::Bytes(0), ty)?.0;
+            self.require_intrinsic(name, nonzero, "division by zero")?;
+
+            // `MIN / -1` overflows.
+            if kind.is_signed_int() {
+                let bool = self.tcx.types.bool;
+                let min = PrimVal::from_i128(-1 << (bits - 1));
+                let not_min = self.binary_op(mir::BinOp::Ne, l, ty, min, ty)?.0;
+                let not_minus_one = self.binary_op(mir::BinOp::Ne, r, ty, PrimVal::from_i128(-1), ty)?.0;
+                let no_overflow = self.binary_op(mir::BinOp::BitOr, not_min, bool, not_minus_one, bool)?.0;
+                self.require_intrinsic(name, no_overflow, "overflow")?;
+            }
+        } else {
+            let below = self.binary_op(mir::BinOp::Lt, r, ty, PrimVal::Bytes(bits), ty)?.0;
+            self.require_intrinsic(name, below, "shift amount out of range")?;
+            if kind.is_signed_int() {
+                let non_negative = self.binary_op(mir::BinOp::Ge, r, ty, PrimVal::Bytes(0), ty)?.0;
+                self.require_intrinsic(name, non_negative, "shift amount out of range")?;
+            }
+        }
+
+        let result = self.binary_op(bin_op, l, ty, r, ty)?.0;
+        if name == "exact_div" {
+            let rem = self.binary_op(mir::BinOp::Rem, l, ty, r, ty)?.0;
+            let exact = self.binary_op(mir::BinOp::Eq, rem, ty, PrimVal::Bytes(0), ty)?.0;
+            self.require_intrinsic(name, exact, "division with a remainder")?;
+        }
+        Ok(result)
+    }
+
+    /// Fails with an `Intrinsic` error for `name` unless the bool `cond` holds. If an
+    /// abstract `cond` can be false, the path where it is false splits off as a side
+    /// error.
+    fn require_intrinsic(&mut self, name: &str, cond: PrimVal, problem: &str) -> EvalResult<'tcx> {
+        let err = EvalError::Intrinsic(format!("{}: {}", name, problem));
+        if cond.is_concrete() {
+            if cond.to_bool()? { Ok(()) } else { Err(err) }
+        } else {
+            self.require_valid(cond, |_, _| Ok(err))
+        }
+    }
+
+    /// The size and alignment of the `ty` that `value` points to. The size is
+    /// abstract if `ty` ends in a slice of abstract length.
     pub fn size_and_align_of_dst(
-        &self,
+        &mut self,
         ty: ty::Ty<'tcx>,
         value: Value,
-    ) -> EvalResult<'tcx, (u64, u64)> {
+    ) -> EvalResult<'tcx, (PrimVal, u64)> {
         if let Some(size) = self.type_size(ty)? {
-            Ok((size as u64, self.type_align(ty)? as u64))
+            Ok((PrimVal::Bytes(size as u128), self.type_align(ty)? as u64))
         } else {
             match ty.sty {
                 ty::TyAdt(def, substs) => {
@@ -513,9 +711,6 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     // is unfixed, we do not yet add the necessary padding
                     // here. But this is where the add would go.)
 
-                    // Return the sum of sizes and max of aligns.
-                    let size = sized_size + unsized_size;
-
                     // Choose max of two known alignments (combined value must
                     // be aligned according to more restrictive of the two).
                     let align = sized_align.max(Align::from_bytes(unsized_align, unsized_align).unwrap());
@@ -531,21 +726,42 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     //
                     //   `(size + (align-1)) & -align`
 
-                    let size = Size::from_bytes(size).abi_align(align).bytes();
+                    // Return the sum of sizes and max of aligns.
+                    let size = match unsized_size {
+                        PrimVal::Abstract(_) => {
+                            let usize = self.tcx.types.usize;
+                            let mask = align.abi() - 1;
+                            let size = self.binary_op(
+                                mir::BinOp::Add, unsized_size, usize, PrimVal::Bytes((sized_size + mask) as u128), usize)?.0;
+                            self.binary_op(mir::BinOp::BitAnd, size, usize, PrimVal::Bytes(!mask as u128), usize)?.0
+                        }
+                        _ => {
+                            let size = sized_size + unsized_size.to_u64()?;
+                            PrimVal::Bytes(Size::from_bytes(size).abi_align(align).bytes() as u128)
+                        }
+                    };
                     Ok((size, align.abi()))
                 }
                 ty::TyDynamic(..) => {
                     let (_, vtable) = value.expect_ptr_vtable_pair(&self.memory)?;
                     // the second entry in the vtable is the dynamic size of the object.
-                    self.read_size_and_align_from_vtable(vtable)
+                    let (size, align) = self.read_size_and_align_from_vtable(vtable)?;
+                    Ok((PrimVal::Bytes(size as u128), align))
                 }
 
                 ty::TySlice(_) | ty::TyStr => {
                     let elem_ty = ty.sequence_element_type(self.tcx);
                     let elem_size = self.type_size(elem_ty)?.expect("slice element must be sized") as u64;
-                    let (_, len) = value.expect_slice(&self.memory)?;
+                    let len = value.expect_slice(&self.memory)?.1;
                     let align = self.type_align(elem_ty)?;
-                    Ok((len * elem_size, align as u64))
+                    let size = match len {
+                        PrimVal::Abstract(_) => {
+                            let usize = self.tcx.types.usize;
+                            self.binary_op(mir::BinOp::Mul, len, usize, PrimVal::Bytes(elem_size as u128), usize)?.0
+                        }
+                        _ => PrimVal::Bytes((len.to_u64()? * elem_size) as u128),
+                    };
+                    Ok((size, align as u64))
                 }
 
                 _ => bug!("size_of_val::<{:?}>", ty),
@@ -565,32 +781,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 fn numeric_intrinsic<'tcx>(
     name: &str,
     val: PrimVal,
-    kind: PrimValKind
+    kind: PrimValKind,
+    constraints: &mut ConstraintContext,
 ) -> EvalResult<'tcx, PrimVal> {
-    macro_rules! integer_intrinsic {
-        ($name:expr, $val:expr, $kind:expr, $method:ident) => ({
-            let val = $val;
-            let bytes = val.to_bytes()?;
-
-            use value::PrimValKind::*;
-            let result_bytes = match $kind {
-                I8 => (bytes as i8).$method() as u128,
-                U8 => (bytes as u8).$method() as u128,
-                I16 => (bytes as i16).$method() as u128,
-                U16 => (bytes as u16).$method() as u128,
-                I32 => (bytes as i32).$method() as u128,
-                U32 => (bytes as u32).$method() as u128,
-                I64 => (bytes as i64).$method() as u128,
-                U64 => (bytes as u64).$method() as u128,
-                I128 => (bytes as i128).$method() as u128,
-                U128 => bytes.$method() as u128,
-                _ => bug!("invalid `{}` argument: {:?}", $name, val),
-            };
-
-            PrimVal::Bytes(result_bytes)
-        });
-    }
-
     let result_val = match name {
         "bswap" => {
             if let PrimVal::Abstract(mut sbytes) = val {
@@ -605,6 +798,14 @@ fn numeric_intrinsic<'tcx>(
                integer_intrinsic!("bswap", val, kind, swap_bytes)
            }
         }
+        "ctlz" | "ctpop" | "cttz" if !val.is_concrete() => {
+            let op = match name {
+                "ctlz" => BitCount::LeadingZeros,
+                "ctpop" => BitCount::Ones,
+                _ => BitCount::TrailingZeros,
+            };
+            constraints.add_bit_count_constraint(op, val, kind)?
+        }
         "ctlz"  => integer_intrinsic!("ctlz",  val, kind, leading_zeros),
         "ctpop" => integer_intrinsic!("ctpop", val, kind, count_ones),
         "cttz"  => integer_intrinsic!("cttz",  val, kind, trailing_zeros),
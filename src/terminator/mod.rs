@@ -74,7 +74,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             mir::BinOp::Eq, discr_kind, discr_prim, prim);
                         otherwise_constraints.push(
                             Constraint::new_compare(mir::BinOp::Ne, discr_kind, discr_prim, prim));
-                        if self.memory.constraints.is_feasible_with(&[eq_constraint]) {
+                        if self.memory.constraints.is_feasible_with(&[eq_constraint])? {
                             feasible_blocks_with_constraints.push(
                                 FinishStep {
                                     constraints: vec![eq_constraint],
@@ -86,7 +86,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         }
                     }
 
-                    if self.memory.constraints.is_feasible_with(&otherwise_constraints) {
+                    if self.memory.constraints.is_feasible_with(&otherwise_constraints)? {
                         feasible_blocks_with_constraints.push(
                             FinishStep {
                                 constraints: otherwise_constraints,
@@ -159,12 +159,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         match *msg {
                             mir::AssertMessage::BoundsCheck { ref len, ref index } => {
                                 let span = terminator.source_info.span;
-                                let len = self.eval_operand_to_primval(len)
-                                    .expect("can't eval len")
-                                    .to_u64()?;
-                                let index = self.eval_operand_to_primval(index)
-                                    .expect("can't eval index")
-                                    .to_u64()?;
+                                let len = self.eval_operand_to_primval(len)?.to_u64()?;
+                                let index = self.eval_operand_to_primval(index)?.to_u64()?;
                                 Err(EvalError::ArrayIndexOutOfBounds(span, len, index))
                             },
                             mir::AssertMessage::Math(ref err) =>
@@ -183,7 +179,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
                     let mut finish_steps = Vec::new();
 
-                    if self.memory.constraints.is_feasible_with(&succeed_constraints[..]) {
+                    if self.memory.constraints.is_feasible_with(&succeed_constraints[..])? {
                         finish_steps.push(
                             FinishStep {
                                 constraints: succeed_constraints,
@@ -194,16 +190,14 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             });
                     }
 
-                    if self.memory.constraints.is_feasible_with(&fail_constraints[..]) {
+                    if self.memory.constraints.is_feasible_with(&fail_constraints[..])? {
                         let e = match *msg {
                             mir::AssertMessage::BoundsCheck { ref len, ref index } => {
                                 let span = terminator.source_info.span;
-                                let len = self.eval_operand_to_primval(len)
-                                    .expect("can't eval len")
-                                    .to_u64()?;
-                                let index = self.eval_operand_to_primval(index)
-                                    .expect("can't eval index")
-                                    .to_u64()?;
+                                let len = self.eval_operand_to_primval(len)?;
+                                let len = self.witness_u64(len, &fail_constraints[..])?;
+                                let index = self.eval_operand_to_primval(index)?;
+                                let index = self.witness_u64(index, &fail_constraints[..])?;
                                 EvalError::ArrayIndexOutOfBounds(span, len, index)
                             },
                             mir::AssertMessage::Math(ref err) =>
@@ -221,12 +215,23 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 }
             },
 
-            DropAndReplace { .. } => unimplemented!(),
-            Resume => unimplemented!(),
+            DropAndReplace { .. } =>
+                Err(EvalError::Unimplemented("DropAndReplace terminator".to_string())),
+            Resume => Err(EvalError::Unimplemented("Resume terminator".to_string())),
             Unreachable => Err(EvalError::Unreachable),
         }
     }
 
+    /// Picks a concrete value for `val` that is consistent with the current path
+    /// and `constraints`, so that errors on abstract values can be described.
+    pub(crate) fn witness_u64(&self, val: PrimVal, constraints: &[Constraint]) -> EvalResult<'tcx, u64> {
+        if val.is_concrete() {
+            val.to_u64()
+        } else {
+            self.memory.constraints.get_satisfying_value(constraints, val, PrimValKind::U64)
+        }
+    }
+
     /// Decides whether it is okay to call the method with signature `real_sig` using signature `sig`.
     /// FIXME: This should take into account the platform-dependent ABI description.
     fn check_sig_compat(
@@ -364,7 +369,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         return self.call_c_abi(instance.def_id(), arg_operands, ret, ty, target);
                     },
                     Abi::Rust | Abi::RustCall => {},
-                    _ => unimplemented!(),
+                    abi => return Err(EvalError::Unimplemented(format!("call with {:?} ABI", abi))),
                 }
                 let mut args = Vec::new();
                 for arg in arg_operands {
@@ -414,7 +419,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                                 match arg_val {
                                     Value::ByRef(ptr) => {
                                         for ((offset, ty), arg_local) in offsets.zip(fields).zip(arg_locals) {
-                                            let arg = Value::ByRef(ptr.offset(offset));
+                                            let arg = Value::ByRef(ptr.offset(offset)?);
                                             let dest = self.eval_lvalue(&mir::Lvalue::Local(arg_local))?;
                                             trace!("writing arg {:?} to {:?} (type: {})", arg, dest, ty);
                                             self.write_value(arg, dest, ty)?;
@@ -437,7 +442,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             bug!("rust-call ABI tuple argument was {:?}, {:?}", arg_ty, layout);
                         }
                     }
-                    _ => unimplemented!(),
+                    abi => return Err(EvalError::Unimplemented(format!("call with {:?} ABI", abi))),
                 }
                 Ok(None)
             },
@@ -481,7 +486,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                         args.remove(0);
                     },
                     Abi::RustCall => {},
-                    _ => unimplemented!(),
+                    abi => return Err(EvalError::Unimplemented(format!("virtual call with {:?} ABI", abi))),
                 };
                 for (arg_local, (arg_val, arg_ty)) in arg_locals.zip(args) {
                     let dest = self.eval_lvalue(&mir::Lvalue::Local(arg_local))?;
@@ -492,7 +497,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             ty::InstanceDef::Virtual(_, idx) => {
                 let ptr_size = self.memory.pointer_size();
                 let (_, vtable) = self.eval_operand(&arg_operands[0])?.expect_ptr_vtable_pair(&self.memory)?;
-                let fn_ptr = self.memory.read_ptr(vtable.offset(ptr_size * (idx as u64 + 3)))?;
+                let fn_ptr = self.memory.read_ptr(vtable.offset(ptr_size * (idx as u64 + 3))?)?;
                 let instance = self.memory.get_fn(fn_ptr.alloc_id)?;
                 let mut arg_operands = arg_operands.to_vec();
                 let ty = self.operand_ty(&arg_operands[0]);
@@ -547,8 +552,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                                 self.memory.write_fresh_abstract_bytes(ptr, len as u64)?;
                                 len
                             }
-                            _ => {
-                                unimplemented!()
+                            ref buf => {
+                                return Err(EvalError::Unimplemented(
+                                    format!("reading stdin into buffer {:?}", buf)));
                             }
                         };
 
@@ -559,7 +565,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
                         // FIXME make this more robust
                         self.memory.write_uint(dest_ptr, 0, 8)?; // discriminant
-                        self.memory.write_uint(dest_ptr.offset(8), num_bytes, 8)?; // payload
+                        self.memory.write_uint(dest_ptr.offset(8)?, num_bytes, 8)?; // payload
 
                         self.goto_block(block);
                         return Ok(true);
@@ -625,7 +631,9 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         Ok(false)
     }
 
-    pub fn read_discriminant_value(&self, adt_ptr: Pointer, adt_ty: Ty<'tcx>) -> EvalResult<'tcx, u128> {
+    /// Reads the discriminant of the enum at `adt_ptr`. The result is abstract if the
+    /// bytes that encode it are.
+    pub fn read_discriminant_value(&mut self, adt_ptr: Pointer, adt_ty: Ty<'tcx>) -> EvalResult<'tcx, PrimVal> {
         use rustc::ty::layout::Layout::*;
         let adt_layout = self.type_layout(adt_ty)?;
         trace!("read_discriminant_value {:#?}", adt_layout);
@@ -633,12 +641,18 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
         let discr_val = match *adt_layout {
             General { discr, .. } | CEnum { discr, signed: false, .. } => {
                 let discr_size = discr.size().bytes();
-                self.memory.read_uint(adt_ptr, discr_size)?.to_u128()?
+                self.memory.read_uint(adt_ptr, discr_size)?
             }
 
             CEnum { discr, signed: true, .. } => {
                 let discr_size = discr.size().bytes();
-                self.memory.read_int(adt_ptr, discr_size)?.to_u128()?
+                match self.memory.read_int(adt_ptr, discr_size)? {
+                    // Memory does not sign-extend abstract values, so do it here, the
+                    // way a concrete negative discriminant is extended.
+                    PrimVal::Abstract(sbytes) => self.cast_abstract(
+                        sbytes, PrimValKind::from_int_size(discr_size), PrimValKind::I128)?,
+                    discr_val => discr_val,
+                }
             }
 
             RawNullablePointer { nndiscr, value } => {
@@ -649,7 +663,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
             StructWrappedNullablePointer { nndiscr, ref discrfield, .. } => {
                 let (offset, ty) = self.nonnull_offset_and_ty(adt_ty, nndiscr, discrfield)?;
-                let nonnull = adt_ptr.offset(offset.bytes());
+                let nonnull = adt_ptr.offset(offset.bytes())?;
                 trace!("struct wrapped nullable pointer type: {}", ty);
                 // only the pointer part of a fat pointer is used for this space optimization
                 let discr_size = self.type_size(ty)?.expect("bad StructWrappedNullablePointer discrfield");
@@ -658,21 +672,116 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
             // The discriminant_value intrinsic returns 0 for non-sum types.
             Array { .. } | FatPointer { .. } | Scalar { .. } | Univariant { .. } |
-            Vector { .. } | UntaggedUnion { .. } => 0,
+            Vector { .. } | UntaggedUnion { .. } => PrimVal::Bytes(0),
         };
 
         Ok(discr_val)
     }
 
-    fn read_nonnull_discriminant_value(&self, ptr: Pointer, nndiscr: u128, discr_size: u64) -> EvalResult<'tcx, u128> {
+    fn read_nonnull_discriminant_value(&mut self, ptr: Pointer, nndiscr: u128, discr_size: u64) -> EvalResult<'tcx, PrimVal> {
         trace!("read_nonnull_discriminant_value: {:?}, {}, {}", ptr, nndiscr, discr_size);
+        assert!(nndiscr == 0 || nndiscr == 1);
         let not_null = match self.memory.read_uint(ptr, discr_size) {
+            Ok(p @ PrimVal::Abstract(_)) => {
+                // The discriminant is `nndiscr` exactly when `p` is not null.
+                let op = if nndiscr == 1 { mir::BinOp::Ne } else { mir::BinOp::Eq };
+                let kind = PrimValKind::from_uint_size(discr_size);
+                let is_one = self.memory.constraints.add_binop_constraint(
+                    op, p, PrimVal::Bytes(0), kind)?;
+                return self.memory.constraints.add_bool_to_int_constraint(is_one);
+            }
             Ok(p) => p.to_u64()? != 0,
             Err(EvalError::ReadPointerAsBytes) => true,
             Err(e) => return Err(e),
         };
-        assert!(nndiscr == 0 || nndiscr == 1);
-        Ok(if not_null { nndiscr } else { 1 - nndiscr })
+        Ok(PrimVal::Bytes(if not_null { nndiscr } else { 1 - nndiscr }))
+    }
+
+    /// `memchr`, or `memrchr` if `reverse` is set. If the first match depends on
+    /// abstract bytes, each feasible match position, and not finding a match at all,
+    /// becomes a branch of its own.
+    fn memchr(
+        &mut self,
+        ptr: Pointer,
+        val: PrimVal,
+        num: u64,
+        reverse: bool,
+        dest: Lvalue<'tcx>,
+        dest_ty: Ty<'tcx>,
+        target: mir::BasicBlock,
+    ) -> EvalResult<'tcx, Option<Vec<FinishStep<'tcx>>>> {
+        // Only the low byte of the `c_int` is searched for.
+        let needle = match val {
+            PrimVal::Abstract(sbytes) => sbytes[0],
+            val => SByte::Concrete(val.to_u128()? as u8),
+        };
+        self.check_abstract_size(ptr, num)?;
+        let bytes = self.memory.read_bytes(ptr, num)?.to_vec();
+
+        let byte_primval = |sbyte: SByte| {
+            let mut sbytes = [SByte::Concrete(0); 16];
+            sbytes[0] = sbyte;
+            PrimVal::Abstract(sbytes)
+        };
+
+        let mut positions: Vec<u64> = (0..num).collect();
+        if reverse {
+            positions.reverse();
+        }
+
+        let mut abstract_branches = Vec::new();
+        let mut no_match_constraints = Vec::new();
+        let mut found = None;
+        for idx in positions {
+            let byte = bytes[idx as usize];
+            if let (SByte::Concrete(b), SByte::Concrete(n)) = (byte, needle) {
+                if b == n {
+                    found = Some(idx);
+                    break;
+                }
+                continue;
+            }
+
+            let mut match_constraints = no_match_constraints.clone();
+            match_constraints.push(Constraint::new_compare(
+                mir::BinOp::Eq, PrimValKind::U8, byte_primval(byte), byte_primval(needle)));
+            if self.memory.constraints.is_feasible_with(&match_constraints)? {
+                abstract_branches.push(FinishStep {
+                    constraints: match_constraints,
+                    variant: FinishStepVariant::Continue {
+                        goto_block: target,
+                        set_lvalue: Some((dest, PrimVal::Ptr(ptr.offset(idx)?), dest_ty)),
+                    },
+                });
+            }
+
+            no_match_constraints.push(Constraint::new_compare(
+                mir::BinOp::Ne, PrimValKind::U8, byte_primval(byte), byte_primval(needle)));
+            if !self.memory.constraints.is_feasible_with(&no_match_constraints)? {
+                // This byte has to match, so no later one is reached.
+                return Ok(Some(abstract_branches));
+            }
+        }
+
+        let result = match found {
+            Some(idx) => PrimVal::Ptr(ptr.offset(idx)?),
+            None => PrimVal::Bytes(0),
+        };
+
+        if no_match_constraints.is_empty() {
+            self.write_primval(dest, result, dest_ty)?;
+            self.goto_block(target);
+            return Ok(None);
+        }
+
+        abstract_branches.push(FinishStep {
+            constraints: no_match_constraints,
+            variant: FinishStepVariant::Continue {
+                goto_block: target,
+                set_lvalue: Some((dest, result, dest_ty)),
+            },
+        });
+        Ok(Some(abstract_branches))
     }
 
     fn call_c_abi(
@@ -698,17 +807,21 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
         match &link_name[..] {
             "__rust_allocate" => {
-                let size = self.value_to_primval(args[0], usize)?.to_u64()?;
+                let requested = self.value_to_primval(args[0], usize)?;
+                let size = self.allocation_size(requested)?;
                 let align = self.value_to_primval(args[1], usize)?.to_u64()?;
                 let ptr = self.memory.allocate(size, align)?;
+                self.memory.set_abstract_size(ptr, requested)?;
                 self.write_primval(dest, PrimVal::Ptr(ptr), dest_ty)?;
                 self.goto_block(target);
             }
 
             "__rust_allocate_zeroed" => {
-                let size = self.value_to_primval(args[0], usize)?.to_u64()?;
+                let requested = self.value_to_primval(args[0], usize)?;
+                let size = self.allocation_size(requested)?;
                 let align = self.value_to_primval(args[1], usize)?.to_u64()?;
                 let ptr = self.memory.allocate(size, align)?;
+                self.memory.set_abstract_size(ptr, requested)?;
                 self.memory.write_repeat(ptr, 0, size)?;
                 self.write_primval(dest, PrimVal::Ptr(ptr), dest_ty)?;
                 self.goto_block(target);
@@ -717,7 +830,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
             "__rust_deallocate" => {
                 let ptr = args[0].read_ptr(&self.memory)?;
                 // FIXME: insert sanity check for size and align?
-                let _old_size = self.value_to_primval(args[1], usize)?.to_u64()?;
+                let _old_size = self.value_to_primval(args[1], usize)?;
                 let _align = self.value_to_primval(args[2], usize)?.to_u64()?;
                 self.memory.deallocate(ptr)?;
                 self.goto_block(target);
@@ -725,9 +838,11 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
 
             "__rust_reallocate" => {
                 let ptr = args[0].read_ptr(&self.memory)?;
-                let size = self.value_to_primval(args[2], usize)?.to_u64()?;
+                let requested = self.value_to_primval(args[2], usize)?;
+                let size = self.allocation_size(requested)?;
                 let align = self.value_to_primval(args[3], usize)?.to_u64()?;
                 let new_ptr = self.memory.reallocate(ptr, size, align)?;
+                self.memory.set_abstract_size(new_ptr, requested)?;
                 self.write_primval(dest, PrimVal::Ptr(new_ptr), dest_ty)?;
                 self.goto_block(target);
             }
@@ -736,6 +851,8 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 let left = args[0].read_ptr(&self.memory)?;
                 let right = args[1].read_ptr(&self.memory)?;
                 let n = self.value_to_primval(args[2], usize)?.to_u64()?;
+                self.check_abstract_size(left, n)?;
+                self.check_abstract_size(right, n)?;
 
                 let mut is_concrete = true;
                 let mut abstract_branches = Vec::new();
@@ -764,21 +881,21 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                             }
                             (SByte::Abstract(a), SByte::Concrete(c)) => {
                                 is_concrete = false;
-                                let mut sbytes = [SByte::Concrete(0); 8];
+                                let mut sbytes = [SByte::Concrete(0); 16];
                                 sbytes[0] = SByte::Abstract(a);
                                 (PrimVal::Abstract(sbytes), PrimVal::from_u128(c as u128))
                             }
                             (SByte::Concrete(c), SByte::Abstract(a)) => {
                                 is_concrete = false;
-                                let mut sbytes = [SByte::Concrete(0); 8];
+                                let mut sbytes = [SByte::Concrete(0); 16];
                                 sbytes[0] = SByte::Abstract(a);
                                 (PrimVal::from_u128(c as u128), PrimVal::Abstract(sbytes))
                             }
                             (SByte::Abstract(aleft), SByte::Abstract(aright)) => {
                                 is_concrete = false;
-                                let mut sbytes_left = [SByte::Concrete(0); 8];
+                                let mut sbytes_left = [SByte::Concrete(0); 16];
                                 sbytes_left[0] = SByte::Abstract(aleft);
-                                let mut sbytes_right = [SByte::Concrete(0); 8];
+                                let mut sbytes_right = [SByte::Concrete(0); 16];
                                 sbytes_right[0] = SByte::Abstract(aright);
                                 (PrimVal::Abstract(sbytes_left), PrimVal::Abstract(sbytes_right))
                             }
@@ -802,7 +919,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                                 mir::BinOp::Gt, PrimValKind::U8,
                                 left, right));
 
-                        if self.memory.constraints.is_feasible_with(&lt_constraints) {
+                        if self.memory.constraints.is_feasible_with(&lt_constraints)? {
                             abstract_branches.push(
                                 FinishStep {
                                     constraints: lt_constraints,
@@ -814,7 +931,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                                 });
                         }
 
-                        if self.memory.constraints.is_feasible_with(&gt_constraints) {
+                        if self.memory.constraints.is_feasible_with(&gt_constraints)? {
                             abstract_branches.push(
                                 FinishStep {
                                     constraints: gt_constraints,
@@ -838,7 +955,7 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                     self.write_primval(dest, PrimVal::Bytes(result as u128), dest_ty)?;
                     self.goto_block(target);
                 } else {
-                    if self.memory.constraints.is_feasible_with(&equal_constraints) {
+                    if self.memory.constraints.is_feasible_with(&equal_constraints)? {
                         abstract_branches.push(FinishStep {
                             constraints: equal_constraints,
                             variant: FinishStepVariant::Continue {
@@ -851,32 +968,13 @@ impl<'a, 'tcx> EvalContext<'a, 'tcx> {
                 }
             }
 
-            "memrchr" => {
-                unimplemented!()
-                    /*
-                let ptr = args[0].read_ptr(&self.memory)?;
-                let val = self.value_to_primval(args[1], usize)?.to_u64()? as u8;
-                let num = self.value_to_primval(args[2], usize)?.to_u64()?;
-                if let Some(idx) = self.memory.read_bytes(ptr, num)?.iter().rev().position(|&c| c == val) {
-                    let new_ptr = ptr.offset(num - idx as u64 - 1);
-                    self.write_value(Value::ByVal(PrimVal::Ptr(new_ptr)), dest, dest_ty)?;
-                } else {
-                    self.write_value(Value::ByVal(PrimVal::Bytes(0)), dest, dest_ty)?;
-                }*/
-            }
-
+            "memrchr" |
             "memchr" => {
-                unimplemented!()
-                /*
                 let ptr = args[0].read_ptr(&self.memory)?;
-                let val = self.value_to_primval(args[1], usize)?.to_u64()? as u8;
+                let val = self.value_to_primval(args[1], usize)?;
                 let num = self.value_to_primval(args[2], usize)?.to_u64()?;
-                if let Some(idx) = self.memory.read_bytes(ptr, num)?.iter().position(|&c| c == val) {
-                    let new_ptr = ptr.offset(idx as u64);
-                    self.write_value(Value::ByVal(PrimVal::Ptr(new_ptr)), dest, dest_ty)?;
-                } else {
-                    self.write_value(Value::ByVal(PrimVal::Bytes(0)), dest, dest_ty)?;
-                }*/
+                let reverse = &link_name[..] == "memrchr";
+                return self.memchr(ptr, val, num, reverse, dest, dest_ty, target);
             }
 
             "getenv" => {
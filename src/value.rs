@@ -47,13 +47,14 @@ pub enum Value {
 /// `memory::Allocation`. It is in many ways like a small chunk of a `Allocation`, up to 8 bytes in
 /// size. Like a range of bytes in an `Allocation`, a `PrimVal` can either represent the raw bytes
 /// of a simple value, a pointer into another `Allocation`, or be undefined.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PrimVal {
     /// The raw bytes of a simple value.
     Bytes(u128),
 
-    /// The bytes of an abstract value, in little endian byteorder.
-    Abstract([SByte; 8]),
+    /// The bytes of an abstract value, in little endian byteorder. Values
+    /// narrower than 16 bytes only use the low bytes.
+    Abstract([SByte; 16]),
 
     /// A pointer into an `Allocation`. An `Allocation` in the `memory` module has a list of
     /// relocations, but a `PrimVal` is only large enough to contain one, so we just represent the
@@ -65,7 +66,7 @@ pub enum PrimVal {
     Undef,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PrimValKind {
     I8, I16, I32, I64, I128,
     U8, U16, U32, U64, U128,
@@ -93,33 +94,34 @@ impl<'a, 'tcx: 'a> Value {
         match *self {
             ByRef(ref_ptr) => {
                 let ptr = mem.read_ptr(ref_ptr)?;
-                let vtable = mem.read_ptr(ref_ptr.offset(mem.pointer_size()))?;
+                let vtable = mem.read_ptr(ref_ptr.offset(mem.pointer_size())?)?;
                 Ok((ptr, vtable))
             }
 
             ByValPair(ptr, vtable) => Ok((ptr.to_ptr()?, vtable.to_ptr()?)),
 
-            _ => bug!("expected ptr and vtable, got {:?}", self),
+            _ => Err(EvalError::Unimplemented(
+                format!("expected ptr and vtable, got {:?}", self))),
         }
     }
 
-    pub(super) fn expect_slice(&self, mem: &Memory<'a, 'tcx>) -> EvalResult<'tcx, (Pointer, u64)> {
+    /// The pointer and the length of a slice. The length may be abstract.
+    pub(super) fn expect_slice(&self, mem: &Memory<'a, 'tcx>) -> EvalResult<'tcx, (Pointer, PrimVal)> {
         use self::Value::*;
         match *self {
             ByRef(ref_ptr) => {
                 let ptr = mem.read_ptr(ref_ptr)?;
-                let len = match mem.read_usize(ref_ptr.offset(mem.pointer_size()))? {
-                    PrimVal::Bytes(n) => n as u64,
-                    _ => unimplemented!(),
-                };
+                let len = mem.read_usize(ref_ptr.offset(mem.pointer_size())?)?;
                 Ok((ptr, len))
             },
+            ByValPair(ptr, len @ PrimVal::Abstract(_)) => Ok((ptr.to_ptr()?, len)),
             ByValPair(ptr, val) => {
                 let len = val.to_u128()?;
                 assert_eq!(len as u64 as u128, len);
-                Ok((ptr.to_ptr()?, len as u64))
+                Ok((ptr.to_ptr()?, PrimVal::Bytes(len)))
             },
-            _ => unimplemented!(),
+            _ => Err(EvalError::Unimplemented(
+                format!("expected ptr and length, got {:?}", self))),
         }
     }
 }
@@ -159,7 +161,8 @@ impl<'tcx> PrimVal {
     pub fn to_bytes(self) -> EvalResult<'tcx, u128> {
         match self {
             PrimVal::Bytes(b) => Ok(b),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::Unimplemented(
+                "expected concrete bytes, got an abstract value".to_string())),
             PrimVal::Ptr(p) => p.to_int().map(|b| b as u128),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
         }
@@ -168,7 +171,8 @@ impl<'tcx> PrimVal {
     pub fn to_ptr(self) -> EvalResult<'tcx, Pointer> {
         match self {
             PrimVal::Bytes(b) => Ok(Pointer::from_int(b as u64)),
-            PrimVal::Abstract(_) => unimplemented!(),
+            PrimVal::Abstract(_) => Err(EvalError::Unimplemented(
+                "expected a pointer, got an abstract value".to_string())),
             PrimVal::Ptr(p) => Ok(p),
             PrimVal::Undef => Err(EvalError::ReadUndefBytes),
         }
@@ -236,10 +240,13 @@ impl PrimValKind {
             I8 | U8 => 1,
             I16 | U16 => 2,
             I32 | U32 => 4,
-            F32 => 4,
+            F32 | Char => 4,
             I64 | U64 => 8,
             F64 => 8,
-            _ => unimplemented!(),
+            I128 | U128 => 16,
+            Bool => 1,
+            // Seer only supports targets with 64-bit pointers.
+            Ptr | FnPtr => 8,
         }
     }
 
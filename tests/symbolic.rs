@@ -7,33 +7,69 @@ fn expect_single_panic(filename: &str, expected_result: Vec<u8>) {
     expect_panics(filename, vec![expected_result]);
 }
 
-fn expect_panics(filename: &str, mut expected_results: Vec<Vec<u8>>) {
+fn expect_panics(filename: &str, expected_results: Vec<Vec<u8>>) {
+    expect_errors(filename, |e| match *e {
+        ::seer::StaticEvalError::Panic => true,
+        _ => false,
+    }, expected_results);
+}
+
+fn expect_single_error<F>(filename: &str, matches: F, expected_result: Vec<u8>)
+    where F: Fn(&::seer::StaticEvalError) -> bool + 'static
+{
+    expect_errors(filename, matches, vec![expected_result]);
+}
+
+fn expect_errors<F>(filename: &str, matches: F, expected_results: Vec<Vec<u8>>)
+    where F: Fn(&::seer::StaticEvalError) -> bool + 'static
+{
+    expect_errors_with_config(filename, ::seer::ExecutionConfig::new(), matches, expected_results);
+}
+
+fn expect_errors_with_config<F>(
+    filename: &str,
+    config: ::seer::ExecutionConfig,
+    matches: F,
+    mut expected_results: Vec<Vec<u8>>)
+    where F: Fn(&::seer::StaticEvalError) -> bool + 'static
+{
+    let mut found: Vec<Vec<u8>> = collect_errors_with_config(filename, config)
+        .into_iter()
+        .filter(|&(_, ref e)| matches(e))
+        .map(|(input, _)| input)
+        .collect();
+    found.sort();
+    expected_results.sort();
+
+    assert_eq!(found, expected_results);
+}
+
+/// Runs `filename` to completion and returns every path that ended in an error,
+/// together with the input that leads there.
+fn collect_errors_with_config(
+    filename: &str,
+    mut config: ::seer::ExecutionConfig)
+    -> Vec<(Vec<u8>, ::seer::StaticEvalError)>
+{
     let found = Rc::new(RefCell::new(Vec::new()));
     let found1 = found.clone();
     let consumer = move |complete| {
-        match complete {
-            ::seer::ExecutionComplete { result: Err(::seer::StaticEvalError::Panic),
-                                        input } => {
-                found1.borrow_mut().push(input);
-                true
-            }
-            _ => true,
+        if let ::seer::ExecutionComplete { result: Err(e), input } = complete {
+            found1.borrow_mut().push((input, e));
         }
+        true
     };
 
     let args = vec!["run_symbolic".to_string(), filename.to_string()];
-    ::seer::ExecutionConfig::new()
+    let status = config
         .consumer(consumer)
         .run(args);
+    assert_eq!(status, ::seer::ExecutionStatus::Complete);
 
-    let mut found = ::std::mem::replace(&mut *found.borrow_mut(), Vec::new());
-    found.sort();
-    expected_results.sort();
-
-    assert_eq!(found, expected_results);
+    let found = ::std::mem::replace(&mut *found.borrow_mut(), Vec::new());
+    found
 }
 
-
 #[test]
 fn symbolic_simple() {
     expect_single_panic("tests/symbolic/simple.rs", vec![43]);
@@ -80,6 +116,164 @@ fn symbolic_read_bool() {
         vec![0, 63]);
 }
 
+#[test]
+fn symbolic_signed_compare() {
+    expect_single_panic("tests/symbolic/signed_compare.rs", vec![0xfb, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn symbolic_unops() {
+    expect_single_panic("tests/symbolic/unops.rs", vec![0xff, 0x00]);
+}
+
+#[test]
+fn symbolic_u128() {
+    expect_single_panic("tests/symbolic/u128.rs", vec![7, 42]);
+}
+
+#[test]
+fn symbolic_float_compare() {
+    expect_single_panic("tests/symbolic/float_compare.rs", vec![0x00, 0x00, 0x20, 0xc0]);
+}
+
+#[test]
+fn symbolic_float_arith() {
+    expect_single_panic("tests/symbolic/float_arith.rs", vec![0x00, 0x00, 0xc0, 0x3f]);
+}
+
+#[test]
+fn symbolic_float_casts() {
+    expect_single_panic("tests/symbolic/float_casts.rs", vec![0xf3]);
+}
+
+#[test]
+fn symbolic_float_edge_cases() {
+    expect_panics("tests/symbolic/float_edge_cases.rs", vec![
+        vec![0, 0xfe],
+        vec![1, 0xff],
+        vec![2, 0x01],
+        vec![3, 0x01],
+        vec![4, 0x03],
+        vec![5, 0xfe],
+        vec![6, 0xff],
+    ]);
+}
+
+#[test]
+fn symbolic_shifts() {
+    expect_single_panic("tests/symbolic/shifts.rs", vec![0xb4, 0x03]);
+}
+
+#[test]
+fn symbolic_casts() {
+    expect_single_panic("tests/symbolic/casts.rs", vec![0xfe, 0x01]);
+}
+
+#[test]
+fn symbolic_invalid_values() {
+    expect_errors("tests/symbolic/invalid_values.rs", |e| match *e {
+        ::seer::StaticEvalError::InvalidBool |
+        ::seer::StaticEvalError::InvalidDiscriminant |
+        ::seer::StaticEvalError::InvalidChar(..) => true,
+        _ => false,
+    }, vec![vec![2], vec![0, 3], vec![0, 4, 0x00, 0xd8]]);
+}
+
+#[test]
+fn symbolic_enum_discriminants() {
+    expect_single_panic("tests/symbolic/enum_discriminants.rs", vec![1, 43, 0]);
+}
+
+#[test]
+fn symbolic_negative_discriminant() {
+    expect_single_panic("tests/symbolic/negative_discriminant.rs", vec![0xff]);
+}
+
+#[test]
+fn symbolic_dispatch() {
+    expect_panics("tests/symbolic/dispatch.rs", vec![vec![3, 7], vec![1, 0, 1, 9]]);
+}
+
+#[test]
+fn symbolic_bytes_beside_pointers() {
+    expect_single_panic("tests/symbolic/bytes_beside_pointers.rs", vec![5]);
+}
+
+#[test]
+fn symbolic_misaligned_pointer_read() {
+    expect_errors("tests/symbolic/misaligned_pointer_read.rs", |e| match *e {
+        ::seer::StaticEvalError::Panic => true,
+        ::seer::StaticEvalError::AlignmentCheckFailed { required, has } => {
+            assert_eq!((required, has), (8, 4));
+            true
+        }
+        _ => false,
+    }, vec![vec![8], vec![12]]);
+}
+
+#[test]
+fn symbolic_bit_intrinsics() {
+    expect_panics("tests/symbolic/bit_intrinsics.rs",
+                  vec![vec![0x54, 0x2d], vec![0, 0, 0x60, 3, 0x0c]]);
+}
+
+#[test]
+fn symbolic_unchecked_intrinsics() {
+    let mut found: Vec<(Vec<u8>, String)> = collect_errors_with_config(
+        "tests/symbolic/unchecked_intrinsics.rs", ::seer::ExecutionConfig::new())
+        .into_iter()
+        .map(|(input, e)| match e {
+            ::seer::StaticEvalError::Intrinsic(msg) => (input, msg),
+            e => panic!("unexpected error {:?} on {:?}", e, input),
+        })
+        .collect();
+    found.sort();
+    let expected: Vec<(Vec<u8>, String)> = vec![
+        (vec![0, 9, 0], "exact_div: division by zero"),
+        (vec![0, 9, 2], "exact_div: division with a remainder"),
+        (vec![1, 0x80, 0xff], "unchecked_div: overflow"),
+        (vec![2, 7, 0], "unchecked_rem: division by zero"),
+        (vec![3, 1, 8], "unchecked_shl: shift amount out of range"),
+        (vec![4, 0x80, 0xff], "unchecked_shr: shift amount out of range"),
+        (vec![5, 0, 0], "ctlz_nonzero: called on zero"),
+        (vec![6, 0, 0], "cttz_nonzero: called on zero"),
+    ].into_iter().map(|(input, msg)| (input, msg.to_string())).collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn symbolic_memchr() {
+    expect_single_panic("tests/symbolic/memchr.rs", vec![1, b',', b'x', 2]);
+}
+
+#[test]
+fn symbolic_slice_len() {
+    expect_panics("tests/symbolic/slice_len.rs", vec![vec![2, 5, 7, 9], vec![3, 3, 3, 3]]);
+}
+
+#[test]
+fn symbolic_alloc() {
+    expect_errors("tests/symbolic/symbolic_alloc.rs", |e| match *e {
+        ::seer::StaticEvalError::Panic => true,
+        ::seer::StaticEvalError::PointerOutOfBounds { size, allocation_size, .. } => {
+            assert_eq!(size, 1);
+            assert!(allocation_size == 2 || allocation_size == 4);
+            true
+        }
+        ::seer::StaticEvalError::Unimplemented(ref msg) =>
+            msg.starts_with("allocation of 5000 bytes"),
+        _ => false,
+    }, vec![vec![3, 4, 6], vec![4, 0, 0], vec![2, 1, 2], vec![0, 0, 0, 0x13, 0x88]]);
+}
+
+#[test]
+fn symbolic_overflow() {
+    expect_errors("tests/symbolic/overflow.rs", |e| match *e {
+        ::seer::StaticEvalError::Math(..) => true,
+        _ => false,
+    }, vec![vec![200], vec![7, 0x9b], vec![7, 3, 0xf1, 0x03]]);
+}
+
 #[test]
 fn symbolic_read_signed_int() {
     expect_single_panic(
@@ -136,3 +330,176 @@ fn symbolic_ptr_offset() {
         "tests/symbolic/ptr_offset.rs",
         vec![2, 4]);
 }
+
+#[test]
+fn symbolic_bounds_check() {
+    expect_single_error(
+        "tests/symbolic/bounds_check.rs",
+        |e| match *e {
+            ::seer::StaticEvalError::ArrayIndexOutOfBounds(_, 4, 10) => true,
+            _ => false,
+        },
+        vec![10]);
+}
+
+#[test]
+fn symbolic_slice_bounds() {
+    expect_errors(
+        "tests/symbolic/slice_bounds.rs",
+        |e| match *e {
+            ::seer::StaticEvalError::ArrayIndexOutOfBounds(_, len, index) => {
+                assert_eq!((len, index), (3, 3));
+                true
+            }
+            ::seer::StaticEvalError::Panic => true,
+            _ => false,
+        },
+        vec![vec![3, 3]]);
+}
+
+#[test]
+fn symbolic_incomplete_path() {
+    expect_single_panic("tests/symbolic/incomplete_path.rs", vec![2, 3]);
+}
+
+#[test]
+fn symbolic_search_strategies() {
+    use seer::SearchStrategyKind::*;
+    for &strategy in &[DepthFirst, BreadthFirst, RandomPath(7), FewestConstraints, CoveringNew] {
+        let mut config = ::seer::ExecutionConfig::new();
+        config.search_strategy(strategy);
+        expect_errors_with_config(
+            "tests/symbolic/comparisons.rs",
+            config,
+            |e| match *e {
+                ::seer::StaticEvalError::Panic => true,
+                _ => false,
+            },
+            vec![vec![17, 18, 38, 37, 101]]);
+    }
+}
+
+/// Explores paths last-in-first-out and counts how often it is consulted.
+struct CountingStrategy<'a, 'tcx: 'a> {
+    paths: Vec<::seer::EvalContext<'a, 'tcx>>,
+    pops: Rc<::std::cell::Cell<usize>>,
+}
+
+impl<'a, 'tcx: 'a> ::seer::SearchStrategy<'a, 'tcx> for CountingStrategy<'a, 'tcx> {
+    fn push(&mut self, ecx: ::seer::EvalContext<'a, 'tcx>) {
+        self.paths.push(ecx);
+    }
+
+    fn pop(&mut self, _coverage: &::seer::Coverage) -> Option<::seer::EvalContext<'a, 'tcx>> {
+        self.pops.set(self.pops.get() + 1);
+        self.paths.pop()
+    }
+
+    fn len(&self) -> usize {
+        self.paths.len()
+    }
+}
+
+#[test]
+fn symbolic_custom_search_strategy() {
+    let pops = Rc::new(::std::cell::Cell::new(0));
+    let pops1 = pops.clone();
+    let mut config = ::seer::ExecutionConfig::new();
+    config.custom_search_strategy(move |_tcx| {
+        Box::new(CountingStrategy { paths: Vec::new(), pops: pops1.clone() })
+    });
+    expect_errors_with_config(
+        "tests/symbolic/comparisons.rs",
+        config,
+        |e| match *e {
+            ::seer::StaticEvalError::Panic => true,
+            _ => false,
+        },
+        vec![vec![17, 18, 38, 37, 101]]);
+    assert!(pops.get() > 0);
+}
+
+#[test]
+fn symbolic_covering_new() {
+    // The panic is in a block that no other path executes. Covering-new search
+    // switches to the path that is about to run it as soon as that path is
+    // pending, so it reaches the panic long before the step budget runs out.
+    let found = Rc::new(RefCell::new(Vec::new()));
+    let found1 = found.clone();
+    let consumer = move |complete| {
+        if let ::seer::ExecutionComplete { result: Err(::seer::StaticEvalError::Panic), input } = complete {
+            found1.borrow_mut().push(input);
+        }
+        true
+    };
+
+    let args = vec!["run_symbolic".to_string(), "tests/symbolic/covering_new.rs".to_string()];
+    ::seer::ExecutionConfig::new()
+        .search_strategy(::seer::SearchStrategyKind::CoveringNew)
+        .max_steps(20_000)
+        .consumer(consumer)
+        .run(args);
+    assert_eq!(*found.borrow(), vec![vec![42]]);
+}
+
+#[test]
+fn symbolic_solver_unknown() {
+    let unknown = Rc::new(::std::cell::Cell::new(0));
+    let unknown1 = unknown.clone();
+    let consumer = move |complete: ::seer::ExecutionComplete| {
+        match complete.result {
+            Err(::seer::StaticEvalError::SolverUnknown) => unknown1.set(unknown1.get() + 1),
+            Err(::seer::StaticEvalError::Panic) => {
+                // Only an actual factorization may reach the panic.
+                let read_u64 = |data: &[u8]| {
+                    data.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+                };
+                let x = read_u64(&complete.input[..8]);
+                let y = read_u64(&complete.input[8..16]);
+                assert_eq!(x.wrapping_mul(y), 0xffffffea00000055);
+            }
+            _ => (),
+        }
+        true
+    };
+
+    // With a timeout of one millisecond, the queries that search for the factors
+    // end as unknown however fast the machine is.
+    let args = vec!["run_symbolic".to_string(), "tests/symbolic/solver_unknown.rs".to_string()];
+    let status = ::seer::ExecutionConfig::new()
+        .solver_timeout(::std::time::Duration::from_millis(1))
+        .consumer(consumer)
+        .run(args);
+    assert_eq!(status, ::seer::ExecutionStatus::Complete);
+    assert!(unknown.get() > 0);
+}
+
+#[test]
+fn symbolic_budgets() {
+    let args = vec!["run_symbolic".to_string(), "tests/symbolic/comparisons.rs".to_string()];
+
+    let mut config = ::seer::ExecutionConfig::new();
+    config.max_steps(10);
+    assert_eq!(config.run(args.clone()),
+               ::seer::ExecutionStatus::Truncated(::seer::Budget::Steps));
+
+    let mut config = ::seer::ExecutionConfig::new();
+    config.max_paths(1);
+    assert_eq!(config.run(args),
+               ::seer::ExecutionStatus::Truncated(::seer::Budget::Paths));
+}
+
+#[test]
+fn symbolic_independent_bytes() {
+    let mut config = ::seer::ExecutionConfig::new();
+    config.max_time(::std::time::Duration::from_secs(60));
+    let expected: Vec<u8> = (0..64).map(|idx| b'a' + (idx % 26) as u8).collect();
+    expect_errors_with_config(
+        "tests/symbolic/independent_bytes.rs",
+        config,
+        |e| match *e {
+            ::seer::StaticEvalError::Panic => true,
+            _ => false,
+        },
+        vec![expected]);
+}
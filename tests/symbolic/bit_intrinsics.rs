@@ -0,0 +1,37 @@
+#![feature(core_intrinsics)]
+
+fn main() {
+    use std::intrinsics::{ctlz_nonzero, cttz_nonzero};
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = data[0];
+    let y = data[1] as u16;
+
+    // should panic on x = 0x54, y = 0x2d, that is, [0x54, 0x2d]
+    if x.count_ones() == 3 && (x as u32).leading_zeros() == 25 && x.trailing_zeros() == 2 {
+        if x.rotate_left(3) == 0xa2 && y.swap_bytes() == 0x2d00 {
+            panic!()
+        }
+    }
+
+    if data[0] != 0 || data[1] != 0 {
+        return;
+    }
+
+    let mut data: Vec<u8> = vec![0; 3];
+    stdin.read(&mut data[..]).unwrap();
+
+    let z = data[0];
+    let n = data[1] as u32;
+
+    // should panic on z = 0x60, n = 3, w = 0x0c, that is, [0, 0, 0x60, 3, 0x0c]
+    if z != 0 && n < 8 {
+        let (leading, trailing) = unsafe { (ctlz_nonzero(z), cttz_nonzero(z)) };
+        if leading == 1 && trailing == 5 && z.rotate_left(n) == 0x03 && data[2].rotate_right(n) == 0x81 {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,12 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    // Make sure there is only one possible way to go out of bounds.
+    if data[0] >= 4 && data[0] != 10 { return }
+
+    let table = [1u8, 2, 3, 4];
+    let _x = table[data[0] as usize];
+}
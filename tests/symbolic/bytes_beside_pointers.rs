@@ -0,0 +1,25 @@
+use std::io::Read;
+
+fn one() -> u8 {
+    1
+}
+
+struct Op {
+    code: [u8; 8],
+    run: fn() -> u8,
+}
+
+fn main() {
+    let mut data = [0; 1];
+    ::std::io::stdin().read(&mut data[..]).unwrap();
+
+    // The allocation holds a pointer, but the bytes that `op.code[i]` can reach
+    // do not, so the read does not need to split the path.
+    let op = Op { code: [3, 1, 4, 1, 5, 9, 2, 6], run: one };
+    let i = data[0] as usize;
+
+    // should panic on i = 5, that is, [5]
+    if i < op.code.len() && op.code[i] == 9 && (op.run)() == 1 {
+        panic!()
+    }
+}
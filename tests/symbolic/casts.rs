@@ -0,0 +1,16 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = data[0] as i8;
+    let b = data[1] == 1;
+
+    // should panic on x = -2, b = true, that is, [0xfe, 0x01]
+    if x as i32 == -2 && (x as i64) >> 40 == -1 {
+        if (b as u8) + (x as u8) == 0xff {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::io::Read;
+
+fn main() {
+    let mut stdin = ::std::io::stdin();
+    let mut first: Vec<u8> = vec![0; 1];
+    stdin.read(&mut first[..]).unwrap();
+
+    if first[0] != 42 {
+        // 2^16 paths that all execute the same few basic blocks.
+        let mut rest: Vec<u8> = vec![0; 16];
+        stdin.read(&mut rest[..]).unwrap();
+        let mut high = 0;
+        for &b in &rest {
+            if b > 127 {
+                high += 1;
+            }
+        }
+        assert!(high <= 16);
+    } else {
+        panic!()
+    }
+}
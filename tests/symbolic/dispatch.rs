@@ -0,0 +1,68 @@
+use std::io::Read;
+
+fn inc(a: u8) -> u8 {
+    a.wrapping_add(1)
+}
+
+fn double(a: u8) -> u8 {
+    a.wrapping_mul(2)
+}
+
+fn check(a: u8) -> u8 {
+    if a == 7 {
+        panic!()
+    }
+    a
+}
+
+static TABLE: [fn(u8) -> u8; 4] = [inc, double, inc, check];
+
+trait Handler {
+    fn handle(&self, a: u8) -> u8;
+}
+
+struct Inc;
+
+impl Handler for Inc {
+    fn handle(&self, a: u8) -> u8 {
+        a.wrapping_add(1)
+    }
+}
+
+struct Check;
+
+impl Handler for Check {
+    fn handle(&self, a: u8) -> u8 {
+        if a == 9 {
+            panic!()
+        }
+        a
+    }
+}
+
+fn read_bytes() -> [u8; 2] {
+    let mut data = [0; 2];
+    ::std::io::stdin().read(&mut data[..]).unwrap();
+    data
+}
+
+fn main() {
+    let data = read_bytes();
+    let op = data[0] as usize;
+
+    // should panic on op = 3, a = 7, that is, [3, 7]
+    if op < TABLE.len() {
+        TABLE[op](data[1]);
+    }
+
+    if op != 1 || data[1] != 0 { return; }
+
+    let handlers: [&Handler; 2] = [&Inc, &Check];
+    let data = read_bytes();
+    let h = data[0] as usize;
+
+    // should panic on h = 1, a = 9, that is, [1, 0, 1, 9]
+    if h < handlers.len() {
+        handlers[h].handle(data[1]);
+    }
+}
@@ -0,0 +1,28 @@
+#[allow(dead_code)]
+enum Token {
+    Num(u8),
+    Op(u8),
+    End,
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 3];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let token = unsafe { &*(data.as_ptr() as *const Token) };
+    match *token {
+        Token::Op(c) if c == b'+' => {}
+        _ => return,
+    }
+
+    let n = data[2] as usize;
+    let opt = unsafe { &*(&n as *const usize as *const Option<&u8>) };
+
+    // should panic on [1, 43, 0]
+    match *opt {
+        None => panic!(),
+        Some(_) => {}
+    }
+}
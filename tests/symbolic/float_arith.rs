@@ -0,0 +1,19 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let bits =
+        (data[0] as u32) |
+        ((data[1] as u32) << 8) |
+        ((data[2] as u32) << 16) |
+        ((data[3] as u32) << 24);
+    let f: f32 = unsafe { ::std::mem::transmute(bits) };
+    let doubled: u32 = unsafe { ::std::mem::transmute(f + f) };
+
+    // should panic on f = 1.5, that is, [0x00, 0x00, 0xc0, 0x3f]
+    if doubled == 0x4040_0000 && f - 0.5 == 1.0 && f * 4.0 == 6.0 && 3.0 / f == 2.0 && f % 1.0 == 0.5 {
+        panic!()
+    }
+}
@@ -0,0 +1,16 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = data[0] as i8;
+    let f = x as f32 / 4.0;
+    let g = f as f64 - 10.0;
+    let n = g as i64;
+
+    // should panic on x = -13, that is, [0xf3]
+    if n == -13 && g < -13.0 && g > -13.5 && g as u8 == 0 {
+        panic!()
+    }
+}
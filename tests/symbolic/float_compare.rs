@@ -0,0 +1,22 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let bits =
+        (data[0] as u32) |
+        ((data[1] as u32) << 8) |
+        ((data[2] as u32) << 16) |
+        ((data[3] as u32) << 24);
+    let f: f32 = unsafe { ::std::mem::transmute(bits) };
+
+    if f.is_nan() || f.is_infinite() {
+        return;
+    }
+
+    // should panic on f = -2.5, that is, [0x00, 0x00, 0x20, 0xc0]
+    if -f == 2.5 && f < 0.0 && f.abs() >= 2.5 {
+        panic!()
+    }
+}
@@ -0,0 +1,72 @@
+#![feature(i128_type)]
+
+fn from_bits(bits: u32) -> f32 {
+    unsafe { ::std::mem::transmute(bits) }
+}
+
+fn main() {
+    use std::f32::{INFINITY, MIN_POSITIVE};
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    // A power of two with biased exponent `data[1]`, or infinity for 0xff.
+    let f = from_bits((data[1] as u32) << 23);
+
+    match data[0] {
+        // should panic on overflow of 2^127 to infinity, that is, [0, 0xfe]
+        0 => if f < INFINITY && f * 2.0 == INFINITY && -f * 2.0 == -INFINITY {
+            panic!()
+        },
+
+        // should panic on infinity minus infinity, that is, [1, 0xff]
+        1 => {
+            let d = f - f;
+            if d != d {
+                panic!()
+            }
+        }
+
+        // should panic on a subnormal quotient, that is, [2, 0x01]
+        2 => {
+            let q = f / 4.0;
+            if q > 0.0 && q < MIN_POSITIVE && q * 4.0 == MIN_POSITIVE {
+                panic!()
+            }
+        }
+
+        // should panic on 2^24 + 1 rounding down to even, that is, [3, 0x01]
+        3 => {
+            let x = 0x0100_0000 | data[1] as u32;
+            if x != 0x0100_0000 && x as f32 == 16777216.0 {
+                panic!()
+            }
+        }
+
+        // should panic on 2^24 + 3 rounding up to even, that is, [4, 0x03]
+        4 => {
+            let x = 0x0100_0000 | data[1] as u32;
+            if x < 16777220 && x as f32 == 16777220.0 {
+                panic!()
+            }
+        }
+
+        // should panic on infinity saturating as u128 and as i128, and on a
+        // negative value as u8, that is, [5, 0xfe]
+        5 => if f < INFINITY && (f * 2.0) as u128 == u128::max_value()
+            && (-f * 2.0) as i128 == i128::min_value() && (-f) as u8 == 0 {
+            panic!()
+        },
+
+        // should panic on NaN converting to 0, that is, [6, 0xff]
+        6 => {
+            let nan = from_bits((data[1] as u32) << 23 | 1);
+            if nan != nan && nan as i32 == 0 && nan as u64 == 0 {
+                panic!()
+            }
+        }
+
+        _ => {}
+    }
+}
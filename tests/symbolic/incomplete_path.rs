@@ -0,0 +1,20 @@
+use std::io::Read;
+
+fn main() {
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    if data[0] == 1 {
+        // Seer can't follow this path, but that shouldn't stop it from
+        // exploring the others.
+        let f = data[1] as f32;
+        if f > 2.0 {
+            return
+        }
+    }
+
+    if data[0] == 2 && data[1] == 3 {
+        panic!()
+    }
+}
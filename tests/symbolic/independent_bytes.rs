@@ -0,0 +1,16 @@
+use std::io::Read;
+
+fn main() {
+    let mut data: Vec<u8> = vec![0; 64];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    // Each check only involves its own byte, so the solver queries along the
+    // way stay small even though the path collects a constraint per byte.
+    for (idx, &b) in data.iter().enumerate() {
+        if b != b'a' + (idx % 26) as u8 {
+            return;
+        }
+    }
+    panic!()
+}
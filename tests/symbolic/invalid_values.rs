@@ -0,0 +1,42 @@
+use std::io::Read;
+
+#[allow(dead_code)]
+enum Color {
+    Red = 1,
+    Green = 2,
+    Blue = 4,
+}
+
+fn read_byte() -> u8 {
+    let mut data = [0; 1];
+    ::std::io::stdin().read(&mut data[..]).unwrap();
+    data[0]
+}
+
+fn main() {
+    let x = read_byte();
+    if x > 2 { return; }
+
+    // should be an invalid bool on x = 2, that is, [2]
+    let b = unsafe { *(&x as *const u8 as *const bool) };
+    if b { return; }
+
+    let y = read_byte();
+    if y < 3 || y > 4 { return; }
+
+    // should be an invalid discriminant on y = 3, that is, [0, 3]
+    let color = unsafe { &*(&y as *const u8 as *const Color) };
+    match *color {
+        Color::Blue => {}
+        _ => return,
+    }
+
+    let lo = read_byte();
+    let hi = read_byte();
+    let z = ((hi as u32) << 8) | lo as u32;
+    if z < 0xd7ff || z > 0xd800 { return; }
+
+    // should be an invalid char on z = 0xd800, that is, [0, 4, 0x00, 0xd8]
+    let c = unsafe { *(&z as *const u32 as *const char) };
+    assert!(c == '\u{d7ff}');
+}
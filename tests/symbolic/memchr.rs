@@ -0,0 +1,23 @@
+use std::io::{BufRead, Read};
+
+extern {
+    fn memrchr(s: *const u8, c: i32, n: usize) -> *const u8;
+}
+
+fn main() {
+    let mut data: Vec<u8> = vec![0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    // `read_until` finds the delimiter with `memchr`.
+    let mut head = Vec::new();
+    (&data[..]).read_until(b',', &mut head).unwrap();
+    let last_x = unsafe { memrchr(data.as_ptr(), b'x' as i32, data.len()) };
+
+    // should panic on [1, ',', 'x', 2]
+    if head.len() == 2 && last_x == unsafe { data.as_ptr().offset(2) } {
+        if data[0] == 1 && data[3] == 2 {
+            panic!()
+        }
+    }
+}
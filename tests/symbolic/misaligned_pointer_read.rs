@@ -0,0 +1,29 @@
+use std::io::Read;
+
+fn inc(a: u8) -> u8 {
+    a.wrapping_add(1)
+}
+
+fn check(a: u8) -> u8 {
+    if a == 7 {
+        panic!()
+    }
+    a
+}
+
+static TABLE: [fn(u8) -> u8; 2] = [inc, check];
+
+fn main() {
+    let mut data = [0; 1];
+    ::std::io::stdin().read(&mut data[..]).unwrap();
+    let k = data[0] as isize;
+
+    // should panic on k = 8, that is, [8], and fail the alignment check
+    // of the read on k = 12, that is, [12]
+    if k == 8 || k == 12 {
+        let f = unsafe {
+            *((&TABLE as *const _ as *const u8).offset(k) as *const fn(u8) -> u8)
+        };
+        f(7);
+    }
+}
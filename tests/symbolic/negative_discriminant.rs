@@ -0,0 +1,20 @@
+#[allow(dead_code)]
+enum Sign {
+    Minus = -1,
+    Plus = 1,
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 1];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let sign = unsafe { &*(data.as_ptr() as *const Sign) };
+
+    // should panic on [0xff]
+    match *sign {
+        Sign::Minus => panic!(),
+        Sign::Plus => {}
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::Read;
+
+fn read_byte() -> u8 {
+    let mut data = [0; 1];
+    ::std::io::stdin().read(&mut data[..]).unwrap();
+    data[0]
+}
+
+fn main() {
+    let x = read_byte();
+
+    // should overflow on x = 200, that is, [200]
+    if x <= 200 {
+        let y = x + 56;
+        assert!(y >= 56);
+    }
+
+    if x != 7 { return; }
+
+    let y = read_byte() as i8;
+
+    // should overflow on y = -101, that is, [7, 0x9b]
+    if y >= -101 {
+        let z = y - 28;
+        assert!(z < 100);
+    }
+
+    if y != 3 { return; }
+
+    let z = (read_byte() as u16) | ((read_byte() as u16) << 8);
+
+    // should overflow on z = 1009, that is, [7, 3, 0xf1, 0x03]
+    if z <= 1009 {
+        let w = z * 65;
+        assert!(w >= z);
+    }
+}
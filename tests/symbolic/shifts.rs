@@ -0,0 +1,16 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = data[0];
+    let n = data[1];
+
+    // should panic on x = 0xb4, n = 3, that is, [0xb4, 0x03]
+    if x >> 2 == 0x2d && x << 6 == 0 && (x as i8) >> 4 == -5 {
+        if n < 8 && (x as u32) << n == 0x5a0 {
+            panic!()
+        }
+    }
+}
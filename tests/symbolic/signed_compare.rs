@@ -0,0 +1,20 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x =
+        ((data[0] as u32) +
+         ((data[1] as u32) << 8) +
+         ((data[2] as u32) << 16) +
+         ((data[3] as u32) << 24)) as i32;
+
+    // should panic on x = -5, that is, [0xfb, 0xff, 0xff, 0xff]
+    if x <= -5 && x > -7 && x != -6 {
+        if x >= 0 {
+            unreachable!();
+        }
+        panic!()
+    }
+}
@@ -0,0 +1,36 @@
+#![feature(advanced_slice_patterns)]
+#![feature(slice_patterns)]
+
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let table = [1u8, 2, 3, 4];
+    let n = data[0] as usize;
+    if n > table.len() { return }
+    let field = &table[..n];
+    let i = data[1] as usize;
+
+    // Make sure there is only one possible way to go out of bounds,
+    // i = n = 3, that is, [3, 3].
+    if i <= n && n + i == 6 {
+        let _x = field[i];
+    }
+    if i != 0 { return }
+
+    // Slice patterns check the length before they index, so these
+    // never go out of bounds.
+    match *field {
+        [_.., last] => assert_eq!(last as usize, n),
+        [] => assert_eq!(n, 0),
+    }
+    match *field {
+        [first, ref middle.., _] => {
+            assert_eq!(first, 1);
+            assert_eq!(middle.len(), n - 2);
+        }
+        _ => assert!(n < 2),
+    }
+}
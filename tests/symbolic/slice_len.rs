@@ -0,0 +1,37 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 4];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    // The first byte is the length of the field that follows it.
+    let n = data[0] as usize;
+    if n < 4 {
+        let field = &data[1..1 + n];
+        let rest = &data[1 + n..];
+
+        // should panic on n = 2, field = [5, 7], rest = [9], that is, [2, 5, 7, 9]
+        if field.len() == 2 && field[0] == 5 && field[1] == 7 {
+            if rest.len() == 1 && rest[0] == 9 {
+                panic!()
+            }
+        }
+
+        // should panic on n = 3, field = [3, 3, 3], that is, [3, 3, 3, 3]
+        let mut matches = 0;
+        for &b in field {
+            if b as usize == n {
+                matches += 1;
+            }
+        }
+        if matches == 3 {
+            panic!()
+        }
+
+        // Dropping a boxed slice asks for its size.
+        let boxed = vec![0u8; n].into_boxed_slice();
+        if ::std::mem::size_of_val(&*boxed) != n {
+            panic!()
+        }
+    }
+}
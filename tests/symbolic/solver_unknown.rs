@@ -0,0 +1,25 @@
+fn read_u64(data: &[u8]) -> u64 {
+    let mut result = 0;
+    for (idx, &b) in data.iter().enumerate() {
+        result += (b as u64) << (8 * idx);
+    }
+    result
+}
+
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 16];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = read_u64(&data[..8]);
+    let y = read_u64(&data[8..]);
+
+    // Factoring the product of two 32-bit primes takes z3 far longer than the
+    // timeout that the test sets.
+    if x > 1 && y > 1 && x < 0x1_0000_0000 && y < 0x1_0000_0000
+        && x.wrapping_mul(y) == 0xffffffea00000055
+    {
+        panic!()
+    }
+}
@@ -0,0 +1,47 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 3];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let n = data[0] as usize;
+    if n > 0 && n <= 8 {
+        let mut buf: Vec<u8> = Vec::with_capacity(n);
+        buf.push(data[1]);
+        buf.push(data[2]);
+
+        // should panic on n = 3, that is, [3, 4, 6]
+        if buf.capacity() == 3 && buf[0] + buf[1] == 10 && buf[1] == 6 {
+            panic!()
+        }
+
+        // the allocation has room for 8 bytes, but writing the fifth byte
+        // is out of bounds for n = 4, that is, [4, 0, 0]
+        if data[1] == 0 && data[2] == 0 && (n == 4 || n == 5) {
+            unsafe {
+                *buf.as_mut_ptr().offset(4) = 1;
+            }
+        }
+
+        // reading at an abstract offset past the requested size is out of bounds
+        // too, for n = 2 and i = 2, that is, [2, 1, 2]
+        if n == 2 && data[1] == 1 && (data[2] == 0 || data[2] == 2) {
+            let i = data[2] as isize;
+            let x = unsafe { *buf.as_ptr().offset(i) };
+            assert!(x == 1);
+        }
+    }
+
+    if n == 0 && data[1] == 0 && data[2] == 0 {
+        let mut more: Vec<u8> = vec![0; 2];
+        stdin.read(&mut more[..]).unwrap();
+
+        // allocating 5000 bytes exceeds the limit on allocations of abstract size,
+        // that is, [0, 0, 0, 0x13, 0x88]
+        let m = (more[0] as usize) << 8 | more[1] as usize;
+        if m == 5000 || m == 10 {
+            let big: Vec<u8> = Vec::with_capacity(m);
+            assert!(big.capacity() >= 10);
+        }
+    }
+}
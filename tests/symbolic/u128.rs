@@ -0,0 +1,15 @@
+#![feature(i128_type)]
+
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = ((data[0] as u128) << 96) + (data[1] as u128) * 1000;
+
+    // should panic on [7, 42]
+    if x == (7u128 << 96) + 42_000 && x < (1u128 << 99) {
+        panic!()
+    }
+}
@@ -0,0 +1,26 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics::{ctlz_nonzero, cttz_nonzero, exact_div};
+use std::intrinsics::{unchecked_div, unchecked_rem, unchecked_shl, unchecked_shr};
+use std::io::Read;
+
+fn main() {
+    let mut data = [0; 3];
+    ::std::io::stdin().read(&mut data[..]).unwrap();
+    let (a, b) = (data[1], data[2]);
+
+    // Each case pins its operands down to a few values, some of which are
+    // undefined behavior for the intrinsic.
+    unsafe {
+        match data[0] {
+            0 if a == 9 && b <= 3 => assert_eq!(exact_div(a, b) * b, a),
+            1 if a == 0x80 && b >= 0xfe => assert_eq!(unchecked_div(a as i8, b as i8), 64),
+            2 if a == 7 && b <= 1 => assert_eq!(unchecked_rem(a, b), 0),
+            3 if a == 1 && (b == 7 || b == 8) => assert_eq!(unchecked_shl(a, b), 0x80),
+            4 if a == 0x80 && (b == 0 || b == 0xff) => assert_eq!(unchecked_shr(a as i8, b as i8), -128),
+            5 if a <= 1 && b == 0 => assert_eq!(ctlz_nonzero(a), 7),
+            6 if (a == 0 || a == 0x80) && b == 0 => assert_eq!(cttz_nonzero(a), 7),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,15 @@
+fn main() {
+    use std::io::Read;
+    let mut data: Vec<u8> = vec![0; 2];
+    let mut stdin = ::std::io::stdin();
+    stdin.read(&mut data[..]).unwrap();
+
+    let x = (data[0] as u16) + ((data[1] as u16) << 8);
+
+    // should panic on x = 255, that is, [0xff, 0x00]
+    if !x == 0xff00 {
+        if -(x as i16) == -255 && -(x as i64) == -255 && !(x as u32) == 0xffff_ff00 {
+            panic!()
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Tests of `ExecutionConfig::workers`. Forking worker processes is only safe
+//! from a single-threaded process, and the libtest harness runs each test on a
+//! thread of its own, so this file has a plain `main` instead.
+
+extern crate seer;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+fn main() {
+    workers_find_panic();
+    failed_worker_is_reported();
+}
+
+fn workers_find_panic() {
+    let found = Rc::new(RefCell::new(Vec::new()));
+    let found1 = found.clone();
+    let consumer = move |complete| {
+        if let ::seer::ExecutionComplete { result: Err(::seer::StaticEvalError::Panic), input } = complete {
+            found1.borrow_mut().push(input);
+        }
+        true
+    };
+
+    let args = vec!["run_symbolic".to_string(), "tests/symbolic/manticore.rs".to_string()];
+    let status = ::seer::ExecutionConfig::new()
+        .workers(3)
+        .consumer(consumer)
+        .run(args);
+    assert_eq!(status, ::seer::ExecutionStatus::Complete);
+    assert_eq!(*found.borrow(),
+               vec![vec![61, 77, 65, 78, 84, 73, 67, 79, 82, 69, 61, 0, 1, 2, 3, 4, 5, 50, 51, 29, 212]]);
+}
+
+/// Panics as soon as the executor asks it for a path.
+struct PanickingStrategy;
+
+impl<'a, 'tcx: 'a> ::seer::SearchStrategy<'a, 'tcx> for PanickingStrategy {
+    fn push(&mut self, _ecx: ::seer::EvalContext<'a, 'tcx>) {}
+
+    fn pop(&mut self, _coverage: &::seer::Coverage) -> Option<::seer::EvalContext<'a, 'tcx>> {
+        panic!("the search strategy failed")
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+}
+
+fn failed_worker_is_reported() {
+    let args = vec!["run_symbolic".to_string(), "tests/symbolic/simple.rs".to_string()];
+    let status = ::seer::ExecutionConfig::new()
+        .workers(2)
+        .custom_search_strategy(|_tcx| Box::new(PanickingStrategy))
+        .run(args);
+    assert_eq!(status, ::seer::ExecutionStatus::WorkerFailed);
+}